@@ -0,0 +1,126 @@
+use chrono::{Datelike, Months, NaiveDate};
+use indexmap::IndexMap;
+use serde::Serialize;
+use crate::error::ApiError;
+use crate::models::{AttendanceCode, AttendanceDay};
+use crate::scraping::extract_attendance_month;
+
+const MAX_MONTHS: i64 = 12;
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct AttendanceTotal {
+    pub description: String,
+    pub days: usize,
+    pub periods: usize,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct AttendanceReport {
+    pub from: String,
+    pub to: String,
+    pub days: Vec<AttendanceDay>,
+    pub totals: IndexMap<String, AttendanceTotal>,
+    pub legend: Vec<AttendanceCode>,
+}
+
+fn parse_date(name: &str, value: &str) -> Result<NaiveDate, ApiError> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| ApiError::BadRequest(format!("'{}' must be a date like 2025-10-01, got '{}'", name, value)))
+}
+
+pub fn date_range(from: Option<&str>, to: Option<&str>, today: NaiveDate) -> Result<(NaiveDate, NaiveDate), ApiError> {
+    let to = match to {
+        Some(value) => parse_date("to", value)?,
+        None => today,
+    };
+    let from = match from {
+        Some(value) => parse_date("from", value)?,
+        None => to.with_day(1).unwrap(),
+    };
+
+    if from > to {
+        return Err(ApiError::BadRequest("'from' must not be after 'to'".to_string()));
+    }
+
+    if month_index(to) - month_index(from) + 1 > MAX_MONTHS {
+        return Err(ApiError::BadRequest(format!("Attendance ranges are limited to {} months", MAX_MONTHS)));
+    }
+
+    Ok((from, to))
+}
+
+fn month_index(date: NaiveDate) -> i64 {
+    i64::from(date.year()) * 12 + i64::from(date.month0())
+}
+
+pub fn months_between(from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+    let mut months = Vec::new();
+    let mut month = from.with_day(1).unwrap();
+
+    while month <= to {
+        months.push(month);
+        match month.checked_add_months(Months::new(1)) {
+            Some(next) => month = next,
+            None => break,
+        }
+    }
+
+    months
+}
+
+pub fn summarize(pages: &[String], from: NaiveDate, to: NaiveDate) -> Result<AttendanceReport, ApiError> {
+    let (from_key, to_key) = (from.format("%Y-%m-%d").to_string(), to.format("%Y-%m-%d").to_string());
+
+    let mut days: Vec<AttendanceDay> = Vec::new();
+    let mut legend: Vec<AttendanceCode> = Vec::new();
+
+    for page in pages {
+        let month = extract_attendance_month(page).ok_or_else(|| ApiError::ParseFailure {
+            page: "MonthlyView.aspx".to_string(),
+        })?;
+
+        for code in month.legend {
+            if !legend.iter().any(|l| l.code == code.code) {
+                legend.push(code);
+            }
+        }
+
+        days.extend(
+            month
+                .days
+                .into_iter()
+                .filter(|d| d.date >= from_key && d.date <= to_key && !days.iter().any(|seen| seen.date == d.date))
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    days.sort_by(|a, b| a.date.cmp(&b.date));
+
+    let mut totals: IndexMap<String, AttendanceTotal> = IndexMap::new();
+    for day in &days {
+        let mut counted: Vec<&str> = Vec::new();
+        for record in &day.records {
+            let total = totals.entry(record.code.clone()).or_insert_with(|| AttendanceTotal {
+                description: record.description.clone(),
+                days: 0,
+                periods: 0,
+            });
+
+            if record.period.is_some() {
+                total.periods += 1;
+            }
+            if !counted.contains(&record.code.as_str()) {
+                total.days += 1;
+                counted.push(&record.code);
+            }
+        }
+    }
+
+    Ok(AttendanceReport {
+        from: from_key,
+        to: to_key,
+        days,
+        totals,
+        legend,
+    })
+}
@@ -1,56 +1,210 @@
 use reqwest::Client;
-use scraper::{Html, Selector};
-use std::collections::HashMap;
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+use crate::districts::District;
+use crate::error::ApiError;
+use crate::fetchers::check_status;
+use crate::upstream;
 
-pub async fn login_handler(username: &str, password: &str, link: &str) -> Result<Client, String> {
-    let client = Client::builder()
+const USERNAME_FIELD: &str = "LogOnDetails.UserName";
+const PASSWORD_FIELD: &str = "LogOnDetails.Password";
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct LoginOption {
+    pub value: String,
+    pub label: String,
+    pub selected: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoginForm {
+    pub action: Option<String>,
+    pub fields: Vec<(String, String)>,
+    pub databases: Vec<LoginOption>,
+    pub verification_options: Vec<LoginOption>,
+}
+
+impl LoginForm {
+    fn set(&mut self, name: &str, value: String) {
+        match self.fields.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value,
+            None => self.fields.push((name.to_string(), value)),
+        }
+    }
+
+    fn choose(options: &[LoginOption], preferred: Option<&str>) -> Option<String> {
+        preferred
+            .and_then(|p| options.iter().find(|o| o.value == p))
+            .or_else(|| options.iter().find(|o| o.selected))
+            .or_else(|| options.first())
+            .map(|o| o.value.clone())
+    }
+
+    pub fn payload(&self, username: &str, password: &str, district: &District) -> Vec<(String, String)> {
+        let mut form = self.clone();
+
+        for (name, value) in &district.login_fields {
+            form.set(name, value.clone());
+        }
+
+        match Self::choose(&self.databases, district.database.as_deref()) {
+            Some(database) => form.set("Database", database),
+            None => {
+                if let Some(database) = &district.database {
+                    form.set("Database", database.clone());
+                }
+            }
+        }
+
+        if let Some(option) = Self::choose(&self.verification_options, Some("UsernamePassword")) {
+            form.set("VerificationOption", option);
+        }
+
+        form.set(USERNAME_FIELD, username.to_string());
+        form.set(PASSWORD_FIELD, password.to_string());
+        form.fields
+    }
+}
+
+fn option_label(element: ElementRef) -> String {
+    element.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub fn parse_login_form(html: &str) -> Result<LoginForm, ApiError> {
+    let doc = Html::parse_document(html);
+    let form_sel = Selector::parse("form").unwrap();
+    let input_sel = Selector::parse("input[name]").unwrap();
+    let select_sel = Selector::parse("select[name]").unwrap();
+    let option_sel = Selector::parse("option").unwrap();
+    let label_sel = Selector::parse("label[for]").unwrap();
+
+    let form = doc
+        .select(&form_sel)
+        .find(|f| f.select(&input_sel).any(|i| i.value().attr("name") == Some(USERNAME_FIELD)))
+        .or_else(|| doc.select(&form_sel).find(|f| {
+            f.select(&input_sel).any(|i| i.value().attr("name") == Some("__RequestVerificationToken"))
+        }))
+        .ok_or_else(|| ApiError::ParseFailure { page: "LogOn".to_string() })?;
+
+    let mut login = LoginForm {
+        action: form.value().attr("action").map(|a| a.to_string()),
+        ..LoginForm::default()
+    };
+
+    for input in form.select(&input_sel) {
+        let el = input.value();
+        let name = el.attr("name").unwrap_or_default();
+        let value = el.attr("value").unwrap_or_default().to_string();
+        let checked = el.attr("checked").is_some();
+
+        match el.attr("type").unwrap_or("text").to_ascii_lowercase().as_str() {
+            "submit" | "button" | "image" | "reset" | "file" => {}
+            "radio" if name == "VerificationOption" => {
+                let label = el
+                    .attr("id")
+                    .and_then(|id| doc.select(&label_sel).find(|l| l.value().attr("for") == Some(id)))
+                    .map(option_label)
+                    .unwrap_or_else(|| value.clone());
+                login.verification_options.push(LoginOption { value, label, selected: checked });
+            }
+            "checkbox" | "radio" => {
+                if checked {
+                    login.set(name, if value.is_empty() { "on".to_string() } else { value });
+                }
+            }
+            _ => {
+                if !login.fields.iter().any(|(n, _)| n == name) {
+                    login.fields.push((name.to_string(), value));
+                }
+            }
+        }
+    }
+
+    for select in form.select(&select_sel) {
+        let name = select.value().attr("name").unwrap_or_default();
+        let options: Vec<LoginOption> = select
+            .select(&option_sel)
+            .map(|o| {
+                let label = option_label(o);
+                LoginOption {
+                    value: o.value().attr("value").map(|v| v.to_string()).unwrap_or_else(|| label.clone()),
+                    label,
+                    selected: o.value().attr("selected").is_some(),
+                }
+            })
+            .collect();
+
+        match name {
+            "Database" => login.databases = options,
+            "VerificationOption" => login.verification_options = options,
+            _ => {
+                if let Some(value) = LoginForm::choose(&options, None) {
+                    login.set(name, value);
+                }
+            }
+        }
+    }
+
+    Ok(login)
+}
+
+fn build_client() -> Result<Client, ApiError> {
+    let settings = upstream::settings();
+
+    Client::builder()
         .cookie_store(true)
+        .connect_timeout(settings.connect_timeout)
+        .read_timeout(settings.read_timeout)
+        .timeout(settings.request_timeout)
         .build()
-        .map_err(|e| format!("Client build error: {}", e))?;
+        .map_err(|e| ApiError::Internal(format!("Client build error: {}", e)))
+}
 
-    let login_url = format!("{}/HomeAccess/Account/LogOn", link.trim_end_matches('/'));
-    let resp = client
-        .get(&login_url)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to GET login page: {}", e))?
+fn login_url(base_url: &str) -> String {
+    format!("{}/HomeAccess/Account/LogOn", base_url.trim_end_matches('/'))
+}
+
+async fn fetch_login_form(client: &Client, base_url: &str) -> Result<(reqwest::Url, LoginForm), ApiError> {
+    let resp = check_status(upstream::send(client.get(login_url(base_url)), "Failed to GET login page").await?)?;
+    let page_url = resp.url().clone();
+
+    let html = resp
         .text()
         .await
-        .map_err(|e| format!("Failed to read login page HTML: {}", e))?;
-
-    let token = {
-        let doc = Html::parse_document(&resp);
-        let selector = Selector::parse("input[name='__RequestVerificationToken']").unwrap();
-        doc.select(&selector)
-            .next()
-            .and_then(|e| e.value().attr("value"))
-            .map(|s| s.to_string())
-            .ok_or("No __RequestVerificationToken found")?
-    };
+        .map_err(|e| ApiError::upstream("Failed to read login page HTML", e))?;
 
-    let mut form = HashMap::new();
-    form.insert("__RequestVerificationToken", token);
-    form.insert("SCKTY00328510CustomEnabled", "True".to_string());
-    form.insert("SCKTY00436568CustomEnabled", "True".to_string());
-    form.insert("Database", "10".to_string());
-    form.insert("VerificationOption", "UsernamePassword".to_string());
-    form.insert("LogOnDetails.UserName", username.to_string());
-    form.insert("tempUN", "".to_string());
-    form.insert("tempPW", "".to_string());
-    form.insert("LogOnDetails.Password", password.to_string());
-
-    let post_resp = client
-        .post(&login_url)
-        .form(&form)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to POST login: {}", e))?;
+    Ok((page_url, parse_login_form(&html)?))
+}
+
+pub async fn login_options(district: &District) -> Result<LoginForm, ApiError> {
+    let client = build_client()?;
+    let (_, form) = fetch_login_form(&client, &district.base_url).await?;
+    Ok(form)
+}
+
+pub async fn login_handler(username: &str, password: &str, district: &District) -> Result<Client, ApiError> {
+    let client = build_client()?;
+    let (page_url, form) = fetch_login_form(&client, &district.base_url).await?;
+
+    let post_url = form
+        .action
+        .as_deref()
+        .filter(|a| !a.is_empty())
+        .and_then(|a| page_url.join(a).ok())
+        .unwrap_or(page_url);
+
+    let post_resp = upstream::send(
+        client.post(post_url).form(&form.payload(username, password, district)),
+        "Failed to POST login",
+    )
+    .await?;
+    // A failing LogOn page also leaves us on LogOn, which must not read as a wrong password.
+    let post_resp = check_status(post_resp)?;
 
     let final_url = post_resp.url().to_string();
     if final_url.contains("LogOn") {
-        Err("Invalid username or password".to_string())
+        Err(ApiError::InvalidCredentials)
     } else {
         Ok(client)
     }
 }
-
@@ -1,12 +1,29 @@
+use chrono::{DateTime, Utc};
 use reqwest::Client;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{OnceCell, RwLock};
+use crate::districts::District;
+use crate::error::ApiError;
+use crate::models::ClassGrades;
+
+const MAX_SNAPSHOTS: usize = 20;
+const SNAPSHOT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
 
 #[derive(Clone)]
 pub struct CachedClient {
     pub client: Client,
+    pub password_hash: [u8; 32],
+    pub expires_at: Instant,
+}
+
+#[derive(Clone)]
+pub struct Session {
+    pub username: String,
+    pub password: String,
+    pub district: District,
     pub expires_at: Instant,
 }
 
@@ -16,20 +33,74 @@ pub struct CachedData {
     pub expires_at: Instant,
 }
 
+#[derive(Clone)]
+pub struct Snapshot {
+    pub etag: String,
+    pub taken_at: DateTime<Utc>,
+    pub gradebook: HashMap<String, ClassGrades>,
+}
+
+struct SnapshotHistory {
+    caller: String,
+    snapshots: Vec<Snapshot>,
+    expires_at: Instant,
+}
+
+type Flight<T> = Arc<OnceCell<Result<T, ApiError>>>;
+
+// Callers that ask for the same key while a fetch is running wait for it and share its result.
+// If the caller running the fetch is dropped, the next waiter runs its own fetch instead.
+#[derive(Clone)]
+struct InFlight<T> {
+    flights: Arc<Mutex<HashMap<String, Flight<T>>>>,
+}
+
+impl<T: Clone> InFlight<T> {
+    fn new() -> Self {
+        Self {
+            flights: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    async fn run<F>(&self, key: String, fetch: F) -> Result<T, ApiError>
+    where
+        F: Future<Output = Result<T, ApiError>>,
+    {
+        let flight = self.flights.lock().unwrap().entry(key.clone()).or_default().clone();
+        let result = flight.get_or_init(|| fetch).await.clone();
+
+        let mut flights = self.flights.lock().unwrap();
+        if flights.get(&key).is_some_and(|current| Arc::ptr_eq(current, &flight)) {
+            flights.remove(&key);
+        }
+        result
+    }
+}
+
 pub struct Cache {
     clients: Arc<RwLock<HashMap<String, CachedClient>>>,
     pages: Arc<RwLock<HashMap<String, CachedData>>>,
+    sessions: Arc<RwLock<HashMap<String, Session>>>,
+    snapshots: Arc<RwLock<HashMap<String, SnapshotHistory>>>,
+    logins: InFlight<Client>,
+    fetches: InFlight<String>,
     client_ttl: Duration,
     page_ttl: Duration,
+    closed_page_ttl: Duration,
 }
 
 impl Cache {
-    pub fn new(client_ttl_secs: u64, page_ttl_secs: u64) -> Self {
+    pub fn new(client_ttl_secs: u64, page_ttl_secs: u64, closed_page_ttl_secs: u64) -> Self {
         Self {
             clients: Arc::new(RwLock::new(HashMap::new())),
             pages: Arc::new(RwLock::new(HashMap::new())),
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            snapshots: Arc::new(RwLock::new(HashMap::new())),
+            logins: InFlight::new(),
+            fetches: InFlight::new(),
             client_ttl: Duration::from_secs(client_ttl_secs),
             page_ttl: Duration::from_secs(page_ttl_secs),
+            closed_page_ttl: Duration::from_secs(closed_page_ttl_secs),
         }
     }
 
@@ -37,26 +108,32 @@ impl Cache {
         format!("{}:{}", username, url)
     }
 
+    fn password_hash(password: &str) -> [u8; 32] {
+        Sha256::digest(password.as_bytes()).into()
+    }
+
     fn make_page_key(username: &str, url: &str, endpoint: &str, params: &str) -> String {
         format!("{}:{}:{}:{}", username, url, endpoint, params)
     }
 
-    pub async fn get_client(&self, username: &str, url: &str) -> Option<Client> {
+    // A cached login is only handed out for the password it was made with.
+    pub async fn get_client(&self, username: &str, url: &str, password: &str) -> Option<Client> {
         let key = Self::make_client_key(username, url);
         let clients = self.clients.read().await;
         
-        if let Some(cached) = clients.get(&key) {
-            if Instant::now() < cached.expires_at {
-                return Some(cached.client.clone());
-            }
+        if let Some(cached) = clients.get(&key)
+            && Instant::now() < cached.expires_at
+            && cached.password_hash == Self::password_hash(password) {
+            return Some(cached.client.clone());
         }
         None
     }
 
-    pub async fn set_client(&self, username: &str, url: &str, client: Client) {
+    pub async fn set_client(&self, username: &str, url: &str, password: &str, client: Client) {
         let key = Self::make_client_key(username, url);
         let cached = CachedClient {
             client,
+            password_hash: Self::password_hash(password),
             expires_at: Instant::now() + self.client_ttl,
         };
         
@@ -64,6 +141,74 @@ impl Cache {
         clients.insert(key, cached);
     }
 
+    // Concurrent logins only share a result when the password matches too.
+    pub async fn get_or_login<F, Fut>(&self, username: &str, url: &str, password: &str, login: F) -> Result<Client, ApiError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Client, ApiError>>,
+    {
+        let hash: String = Self::password_hash(password).iter().map(|b| format!("{:02x}", b)).collect();
+        let key = format!("{}:{}", Self::make_client_key(username, url), hash);
+
+        self.logins
+            .run(key, async {
+                if let Some(client) = self.get_client(username, url, password).await {
+                    return Ok(client);
+                }
+                let client = login().await?;
+                self.set_client(username, url, password, client.clone()).await;
+                Ok(client)
+            })
+            .await
+    }
+
+    pub async fn remove_client(&self, username: &str, url: &str) {
+        let key = Self::make_client_key(username, url);
+        let mut clients = self.clients.write().await;
+        clients.remove(&key);
+    }
+
+    pub fn session_ttl(&self) -> Duration {
+        self.client_ttl
+    }
+
+    pub async fn create_session(&self, username: &str, password: &str, district: &District) -> String {
+        let bytes: [u8; 32] = rand::random();
+        let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+        let session = Session {
+            username: username.to_string(),
+            password: password.to_string(),
+            district: district.clone(),
+            expires_at: Instant::now() + self.client_ttl,
+        };
+
+        let mut sessions = self.sessions.write().await;
+        sessions.insert(token.clone(), session);
+        token
+    }
+
+    // Sessions slide: each use pushes the expiry out by another TTL.
+    pub async fn get_session(&self, token: &str) -> Option<Session> {
+        let mut sessions = self.sessions.write().await;
+
+        if let Some(session) = sessions.get_mut(token)
+            && Instant::now() < session.expires_at {
+            session.expires_at = Instant::now() + self.client_ttl;
+            return Some(session.clone());
+        }
+        None
+    }
+
+    pub async fn remove_session(&self, token: &str) -> Option<Session> {
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.remove(token)?;
+
+        let mut snapshots = self.snapshots.write().await;
+        snapshots.retain(|_, history| history.caller != token);
+        Some(session)
+    }
+
     pub async fn get_page(
         &self,
         username: &str,
@@ -74,10 +219,9 @@ impl Cache {
         let key = Self::make_page_key(username, url, endpoint, params);
         let pages = self.pages.read().await;
         
-        if let Some(cached) = pages.get(&key) {
-            if Instant::now() < cached.expires_at {
-                return Some(cached.data.clone());
-            }
+        if let Some(cached) = pages.get(&key)
+            && Instant::now() < cached.expires_at {
+            return Some(cached.data.clone());
         }
         None
     }
@@ -89,17 +233,132 @@ impl Cache {
         endpoint: &str,
         params: &str,
         data: String,
+    ) {
+        self.store_page(username, url, endpoint, params, data, self.page_ttl).await;
+    }
+
+    async fn store_page(
+        &self,
+        username: &str,
+        url: &str,
+        endpoint: &str,
+        params: &str,
+        data: String,
+        ttl: Duration,
     ) {
         let key = Self::make_page_key(username, url, endpoint, params);
         let cached = CachedData {
             data,
-            expires_at: Instant::now() + self.page_ttl,
+            expires_at: Instant::now() + ttl,
         };
         
         let mut pages = self.pages.write().await;
         pages.insert(key, cached);
     }
 
+    pub async fn get_or_fetch_page<F, Fut>(
+        &self,
+        username: &str,
+        url: &str,
+        endpoint: &str,
+        params: &str,
+        fetch: F,
+    ) -> Result<String, ApiError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String, ApiError>>,
+    {
+        self.fetch_into_cache(username, url, endpoint, params, || async { Ok((fetch().await?, self.page_ttl)) })
+            .await
+    }
+
+    // For grading period pages: the fetch also reports whether the period has closed, and
+    // closed ones, which can no longer change, are kept for closed_page_ttl.
+    pub async fn get_or_fetch_run_page<F, Fut>(
+        &self,
+        username: &str,
+        url: &str,
+        endpoint: &str,
+        params: &str,
+        fetch: F,
+    ) -> Result<String, ApiError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(String, bool), ApiError>>,
+    {
+        self.fetch_into_cache(username, url, endpoint, params, || async {
+            let (html, closed) = fetch().await?;
+            Ok((html, if closed { self.closed_page_ttl } else { self.page_ttl }))
+        })
+        .await
+    }
+
+    async fn fetch_into_cache<F, Fut>(
+        &self,
+        username: &str,
+        url: &str,
+        endpoint: &str,
+        params: &str,
+        fetch: F,
+    ) -> Result<String, ApiError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(String, Duration), ApiError>>,
+    {
+        let key = Self::make_page_key(username, url, endpoint, params);
+
+        self.fetches
+            .run(key, async {
+                if let Some(cached) = self.get_page(username, url, endpoint, params).await {
+                    return Ok(cached);
+                }
+                let (html, ttl) = fetch().await?;
+                self.store_page(username, url, endpoint, params, html.clone(), ttl).await;
+                Ok(html)
+            })
+            .await
+    }
+
+    fn make_snapshot_key(username: &str, url: &str, caller: &str) -> String {
+        format!("{}:{}:{}", username, url, caller)
+    }
+
+    // Each caller (a bearer session token, or "" for query credentials) keeps its own history,
+    // so one client's polls don't move another's baseline.
+    pub async fn get_snapshots(&self, username: &str, url: &str, caller: &str) -> Vec<Snapshot> {
+        let key = Self::make_snapshot_key(username, url, caller);
+        let snapshots = self.snapshots.read().await;
+
+        match snapshots.get(&key) {
+            Some(history) if Instant::now() < history.expires_at => history.snapshots.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    // Keeps the newest MAX_SNAPSHOTS distinct gradebooks; an unchanged gradebook only extends the history's life.
+    pub async fn push_snapshot(&self, username: &str, url: &str, caller: &str, snapshot: Snapshot) {
+        let key = Self::make_snapshot_key(username, url, caller);
+        let mut snapshots = self.snapshots.write().await;
+
+        let history = snapshots.entry(key).or_insert_with(|| SnapshotHistory {
+            caller: caller.to_string(),
+            snapshots: Vec::new(),
+            expires_at: Instant::now(),
+        });
+        if Instant::now() >= history.expires_at {
+            history.snapshots.clear();
+        }
+        history.expires_at = Instant::now() + SNAPSHOT_TTL;
+
+        if history.snapshots.last().is_some_and(|last| last.etag == snapshot.etag) {
+            return;
+        }
+        history.snapshots.push(snapshot);
+        if history.snapshots.len() > MAX_SNAPSHOTS {
+            history.snapshots.remove(0);
+        }
+    }
+
     pub async fn clear_expired(&self) {
         let now = Instant::now();
         
@@ -108,6 +367,13 @@ impl Cache {
         
         let mut pages = self.pages.write().await;
         pages.retain(|_, v| now < v.expires_at);
+
+        let mut sessions = self.sessions.write().await;
+        sessions.retain(|_, v| now < v.expires_at);
+
+        // A session's history goes with the session.
+        let mut snapshots = self.snapshots.write().await;
+        snapshots.retain(|_, v| now < v.expires_at && (v.caller.is_empty() || sessions.contains_key(&v.caller)));
     }
     
 }
@@ -117,8 +383,13 @@ impl Clone for Cache {
         Self {
             clients: Arc::clone(&self.clients),
             pages: Arc::clone(&self.pages),
+            sessions: Arc::clone(&self.sessions),
+            snapshots: Arc::clone(&self.snapshots),
+            logins: self.logins.clone(),
+            fetches: self.fetches.clone(),
             client_ttl: self.client_ttl,
             page_ttl: self.page_ttl,
+            closed_page_ttl: self.closed_page_ttl,
         }
     }
 }
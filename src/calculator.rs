@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::error::ApiError;
+use crate::grades::{Grade, Score};
+use crate::models::{Assignment, ClassGrades};
+
+#[derive(Deserialize)]
+pub struct WhatIfRequest {
+    pub class: String,
+    #[serde(default)]
+    pub edits: Vec<ScoreEdit>,
+    #[serde(default)]
+    pub drops: Vec<String>,
+    #[serde(default)]
+    pub additions: Vec<NewAssignment>,
+}
+
+#[derive(Deserialize)]
+pub struct ScoreEdit {
+    pub name: String,
+    pub score: f64,
+    pub total_points: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct NewAssignment {
+    pub name: Option<String>,
+    pub category: String,
+    pub score: f64,
+    pub total_points: Option<f64>,
+    pub weight: Option<f64>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct CategoryResult {
+    pub name: String,
+    pub weight: f64,
+    pub points: f64,
+    pub max_points: f64,
+    pub percent: Option<f64>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct WhatIfResult {
+    pub class: String,
+    pub reported_average: Grade,
+    pub current_average: Option<f64>,
+    pub projected_average: Option<f64>,
+    pub categories: Vec<CategoryResult>,
+}
+
+struct Entry {
+    name: String,
+    category: String,
+    weight: f64,
+    earned: Option<f64>,
+    possible: f64,
+}
+
+impl Entry {
+    fn from_assignment(a: &Assignment) -> Self {
+        let weight = a.weight.unwrap_or(1.0);
+        let possible = a.total_points.unwrap_or(0.0) * weight;
+        let earned = match a.score.score {
+            Score::Points(p) => Some(p * weight),
+            Score::Missing => Some(0.0),
+            _ => None,
+        };
+
+        Self {
+            name: a.name.clone(),
+            category: a.category.clone(),
+            weight,
+            earned,
+            possible,
+        }
+    }
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn weighted_average(class: &ClassGrades, entries: &[Entry]) -> (Option<f64>, Vec<CategoryResult>) {
+    let mut results = Vec::new();
+    let mut total = 0.0;
+    let mut total_weight = 0.0;
+
+    for category in &class.categories {
+        let weight = category.weight.unwrap_or(0.0);
+        let (points, max_points) = entries
+            .iter()
+            .filter(|e| e.category.eq_ignore_ascii_case(&category.name))
+            .filter_map(|e| e.earned.map(|earned| (earned, e.possible)))
+            .fold((0.0, 0.0), |(p, m), (earned, possible)| (p + earned, m + possible));
+
+        let percent = if max_points > 0.0 {
+            Some(round2(points / max_points * 100.0))
+        } else {
+            None
+        };
+
+        if let Some(pct) = percent {
+            total += pct * weight;
+            total_weight += weight;
+        }
+
+        results.push(CategoryResult {
+            name: category.name.clone(),
+            weight,
+            points,
+            max_points,
+            percent,
+        });
+    }
+
+    let average = if total_weight > 0.0 {
+        Some(round2(total / total_weight))
+    } else {
+        None
+    };
+
+    (average, results)
+}
+
+fn find_entries<'a>(entries: &'a mut [Entry], name: &str) -> Result<Vec<&'a mut Entry>, ApiError> {
+    let matched: Vec<&mut Entry> = entries
+        .iter_mut()
+        .filter(|e| e.name.eq_ignore_ascii_case(name.trim()))
+        .collect();
+
+    if matched.is_empty() {
+        Err(ApiError::BadRequest(format!("No assignment named '{}' in this class", name)))
+    } else {
+        Ok(matched)
+    }
+}
+
+pub fn find_class<'a>(
+    gradebook: &'a HashMap<String, ClassGrades>,
+    query: &str,
+) -> Result<(&'a String, &'a ClassGrades), ApiError> {
+    let needle = query.trim().to_lowercase();
+
+    if let Some(found) = gradebook.iter().find(|(name, _)| name.to_lowercase() == needle) {
+        return Ok(found);
+    }
+
+    let matches: Vec<_> = gradebook
+        .iter()
+        .filter(|(name, _)| name.to_lowercase().contains(&needle))
+        .collect();
+
+    match matches.as_slice() {
+        [found] => Ok(*found),
+        [] => Err(ApiError::NotFound(format!("No class matching '{}'", query))),
+        _ => Err(ApiError::BadRequest(format!("More than one class matches '{}'", query))),
+    }
+}
+
+pub fn what_if(class_name: &str, class: &ClassGrades, request: &WhatIfRequest) -> Result<WhatIfResult, ApiError> {
+    let mut entries: Vec<Entry> = class.assignments.iter().map(Entry::from_assignment).collect();
+    let (current_average, _) = weighted_average(class, &entries);
+
+    for edit in &request.edits {
+        for entry in find_entries(&mut entries, &edit.name)? {
+            if let Some(total) = edit.total_points {
+                entry.possible = total * entry.weight;
+            }
+            entry.earned = Some(edit.score * entry.weight);
+        }
+    }
+
+    for drop in &request.drops {
+        find_entries(&mut entries, drop)?;
+        entries.retain(|e| !e.name.eq_ignore_ascii_case(drop.trim()));
+    }
+
+    for (i, addition) in request.additions.iter().enumerate() {
+        let category = class
+            .categories
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(addition.category.trim()))
+            .ok_or_else(|| ApiError::BadRequest(format!("Unknown category '{}' for this class", addition.category)))?;
+
+        let weight = addition.weight.unwrap_or(1.0);
+        entries.push(Entry {
+            name: addition.name.clone().unwrap_or_else(|| format!("What-if {}", i + 1)),
+            category: category.name.clone(),
+            weight,
+            earned: Some(addition.score * weight),
+            possible: addition.total_points.unwrap_or(100.0) * weight,
+        });
+    }
+
+    let (projected_average, categories) = weighted_average(class, &entries);
+
+    Ok(WhatIfResult {
+        class: class_name.to_string(),
+        reported_average: class.average.clone(),
+        current_average,
+        projected_average,
+        categories,
+    })
+}
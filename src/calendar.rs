@@ -0,0 +1,117 @@
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use std::collections::HashMap;
+use crate::models::Assignment;
+use crate::scraping::extract_assignments_typed;
+
+const PRODID: &str = "-//hac-api//Assignments//EN";
+
+// FNV-1a keeps UIDs identical across builds and restarts, which std's hasher doesn't promise.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// RFC 5545 caps content lines at 75 octets; continuation lines start with a space.
+fn fold_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn describe(assignment: &Assignment) -> String {
+    let mut lines = vec![format!("Category: {}", assignment.category)];
+
+    let score = match (assignment.score.raw.as_str(), assignment.total_points) {
+        ("", _) => "Not graded".to_string(),
+        (raw, Some(total)) => format!("{}/{}", raw, total),
+        (raw, None) => raw.to_string(),
+    };
+    lines.push(format!("Score: {}", score));
+
+    if !assignment.percentage.raw.is_empty() {
+        lines.push(format!("Percentage: {}", assignment.percentage.raw));
+    }
+
+    let flags: Vec<&str> = [(assignment.missing, "Missing"), (assignment.exempt, "Exempt"), (assignment.late, "Late")]
+        .into_iter()
+        .filter_map(|(set, label)| set.then_some(label))
+        .collect();
+    if !flags.is_empty() {
+        lines.push(flags.join(", "));
+    }
+
+    lines.join("\n")
+}
+
+// The due date is left out of the UID so a moved due date updates the event instead of adding one.
+fn event_uid(owner: &str, class: &str, assignment: &Assignment, occurrence: usize) -> String {
+    let key = format!("{}\n{}\n{}\n{}\n{}", owner, class, assignment.name, assignment.date_assigned, occurrence);
+    format!("{:016x}@hac-api", fnv1a(&key))
+}
+
+pub fn assignments_calendar(html: &str, short: bool, owner: &str, stamp: DateTime<Utc>) -> String {
+    let mut classes: Vec<(String, Vec<Assignment>)> = extract_assignments_typed(html, short).into_iter().collect();
+    classes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut out = String::new();
+    for line in ["BEGIN:VCALENDAR", "VERSION:2.0", &format!("PRODID:{}", PRODID), "CALSCALE:GREGORIAN", "METHOD:PUBLISH", "X-WR-CALNAME:Assignments"] {
+        fold_line(&mut out, line);
+    }
+
+    let dtstamp = stamp.format("%Y%m%dT%H%M%SZ").to_string();
+
+    for (class, assignments) in &classes {
+        let mut seen: HashMap<(&str, &str), usize> = HashMap::new();
+
+        for assignment in assignments {
+            let occurrence = seen.entry((&assignment.name, &assignment.date_assigned)).or_insert(0);
+            *occurrence += 1;
+
+            let Ok(due) = NaiveDate::parse_from_str(assignment.date_due.trim(), "%m/%d/%Y") else {
+                continue;
+            };
+            let end = due.checked_add_days(Days::new(1)).unwrap_or(due);
+
+            for line in [
+                "BEGIN:VEVENT".to_string(),
+                format!("UID:{}", event_uid(owner, class, assignment, *occurrence)),
+                format!("DTSTAMP:{}", dtstamp),
+                format!("DTSTART;VALUE=DATE:{}", due.format("%Y%m%d")),
+                format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")),
+                format!("SUMMARY:{}", escape_text(&format!("{}: {}", class, assignment.name))),
+                format!("DESCRIPTION:{}", escape_text(&describe(assignment))),
+                format!("CATEGORIES:{}", escape_text(&assignment.category)),
+                "TRANSP:TRANSPARENT".to_string(),
+                "END:VEVENT".to_string(),
+            ] {
+                fold_line(&mut out, &line);
+            }
+        }
+    }
+
+    fold_line(&mut out, "END:VCALENDAR");
+    out
+}
@@ -0,0 +1,166 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use crate::cache::Snapshot;
+use crate::error::ApiError;
+use crate::grades::Grade;
+use crate::models::{Assignment, ClassGrades};
+use crate::scraping::shorten_class_name;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    NewClass,
+    RemovedClass,
+    AverageChanged,
+    NewAssignment,
+    RemovedAssignment,
+    ScoreChanged,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct GradeChange {
+    pub kind: ChangeKind,
+    pub class: String,
+    pub assignment: Option<String>,
+    pub old: Option<String>,
+    pub new: Option<String>,
+    pub delta: Option<f64>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ChangeReport {
+    pub etag: String,
+    pub previous_etag: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub checked_at: DateTime<Utc>,
+    pub changes: Vec<GradeChange>,
+}
+
+// Snapshots only live in memory, so the hash just has to agree with itself within one process.
+pub fn gradebook_etag(gradebook: &HashMap<String, ClassGrades>) -> String {
+    let mut hasher = DefaultHasher::new();
+    json!(gradebook).to_string().hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+pub fn select_baseline<'a>(history: &'a [Snapshot], since: Option<&str>) -> Result<Option<&'a Snapshot>, ApiError> {
+    let Some(since) = since.map(str::trim) else {
+        return Ok(history.last());
+    };
+
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(since) {
+        let timestamp = timestamp.with_timezone(&Utc);
+        return Ok(history.iter().rev().find(|s| s.taken_at <= timestamp).or(history.first()));
+    }
+
+    let etag = since.trim_matches('"');
+    history
+        .iter()
+        .find(|s| s.etag == etag)
+        .map(Some)
+        .ok_or_else(|| ApiError::BadRequest(format!(
+            "Unknown snapshot '{}', it may have expired; call /api/changes without since to start over",
+            etag
+        )))
+}
+
+fn score_value(grade: &Grade) -> Option<String> {
+    Some(grade.raw.clone()).filter(|raw| !raw.is_empty())
+}
+
+fn delta(old: &Grade, new: &Grade) -> Option<f64> {
+    let (old, new) = (old.score.points()?, new.score.points()?);
+    Some(((new - old) * 100.0).round() / 100.0)
+}
+
+// Assignments are matched on name and assigned date; repeats of the same pair are told apart by order.
+fn keyed(assignments: &[Assignment]) -> Vec<((&str, &str, usize), &Assignment)> {
+    let mut seen: HashMap<(&str, &str), usize> = HashMap::new();
+    assignments
+        .iter()
+        .map(|a| {
+            let occurrence = seen.entry((&a.name, &a.date_assigned)).or_insert(0);
+            *occurrence += 1;
+            ((a.name.as_str(), a.date_assigned.as_str(), *occurrence), a)
+        })
+        .collect()
+}
+
+fn diff_class(class: &str, old: &ClassGrades, new: &ClassGrades, changes: &mut Vec<GradeChange>) {
+    let change = |kind, assignment: Option<&str>, old, new, delta| GradeChange {
+        kind,
+        class: class.to_string(),
+        assignment: assignment.map(str::to_string),
+        old,
+        new,
+        delta,
+    };
+
+    if old.average.raw != new.average.raw {
+        changes.push(change(
+            ChangeKind::AverageChanged,
+            None,
+            score_value(&old.average),
+            score_value(&new.average),
+            delta(&old.average, &new.average),
+        ));
+    }
+
+    let old_assignments = keyed(&old.assignments);
+    let new_assignments = keyed(&new.assignments);
+
+    for (key, assignment) in &new_assignments {
+        match old_assignments.iter().find(|(k, _)| k == key) {
+            None => changes.push(change(ChangeKind::NewAssignment, Some(&assignment.name), None, score_value(&assignment.score), None)),
+            Some((_, previous)) if previous.score.raw != assignment.score.raw => changes.push(change(
+                ChangeKind::ScoreChanged,
+                Some(&assignment.name),
+                score_value(&previous.score),
+                score_value(&assignment.score),
+                delta(&previous.score, &assignment.score),
+            )),
+            Some(_) => {}
+        }
+    }
+
+    for (key, assignment) in &old_assignments {
+        if !new_assignments.iter().any(|(k, _)| k == key) {
+            changes.push(change(ChangeKind::RemovedAssignment, Some(&assignment.name), score_value(&assignment.score), None, None));
+        }
+    }
+}
+
+pub fn diff(old: &HashMap<String, ClassGrades>, new: &HashMap<String, ClassGrades>, short: bool) -> Vec<GradeChange> {
+    let classes: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    let mut changes = Vec::new();
+
+    for class in classes {
+        let name = if short { shorten_class_name(class) } else { class.clone() };
+
+        match (old.get(class), new.get(class)) {
+            (Some(old), Some(new)) => diff_class(&name, old, new, &mut changes),
+            (None, Some(new)) => changes.push(GradeChange {
+                kind: ChangeKind::NewClass,
+                class: name,
+                assignment: None,
+                old: None,
+                new: score_value(&new.average),
+                delta: None,
+            }),
+            (Some(old), None) => changes.push(GradeChange {
+                kind: ChangeKind::RemovedClass,
+                class: name,
+                assignment: None,
+                old: score_value(&old.average),
+                new: None,
+                delta: None,
+            }),
+            (None, None) => {}
+        }
+    }
+
+    changes
+}
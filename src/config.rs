@@ -0,0 +1,253 @@
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+use crate::upstream;
+use crate::webhooks;
+
+const LOG_LEVELS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub bind: String,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
+    pub login_ttl_secs: u64,
+    pub page_ttl_secs: u64,
+    pub closed_page_ttl_secs: u64,
+    pub cleaner_interval_secs: u64,
+    pub webhook_interval_secs: u64,
+    pub webhook_attempts: u64,
+    pub webhook_backoff_secs: u64,
+    pub webhook_ttl_secs: u64,
+    pub webhook_allow_private_urls: bool,
+    pub default_link: Option<String>,
+    pub districts_file: Option<String>,
+    pub gpa_rules_file: Option<String>,
+    pub graduation_plan_file: Option<String>,
+    pub allowed_districts: Vec<String>,
+    pub connect_timeout_secs: u64,
+    pub read_timeout_secs: u64,
+    pub request_timeout_secs: u64,
+    pub retry_attempts: u64,
+    pub retry_base_delay_ms: u64,
+    pub breaker_threshold: u64,
+    pub breaker_cooldown_secs: u64,
+    pub log_level: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bind: "0.0.0.0:3000".to_string(),
+            tls_cert: None,
+            tls_key: None,
+            login_ttl_secs: 30 * 60,
+            page_ttl_secs: 5 * 60,
+            closed_page_ttl_secs: 24 * 60 * 60,
+            cleaner_interval_secs: 10 * 60,
+            webhook_interval_secs: 15 * 60,
+            webhook_attempts: 5,
+            webhook_backoff_secs: 30,
+            webhook_ttl_secs: 7 * 24 * 60 * 60,
+            webhook_allow_private_urls: false,
+            default_link: None,
+            districts_file: None,
+            gpa_rules_file: None,
+            graduation_plan_file: None,
+            allowed_districts: Vec::new(),
+            connect_timeout_secs: 10,
+            read_timeout_secs: 20,
+            request_timeout_secs: 30,
+            retry_attempts: 3,
+            retry_base_delay_ms: 200,
+            breaker_threshold: 5,
+            breaker_cooldown_secs: 30,
+            log_level: "info".to_string(),
+        }
+    }
+}
+
+fn parse_number(var: &str, unit: &str, value: &str) -> Result<u64, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("{}: expected a whole number of {}, got '{}'", var, unit, value))
+}
+
+fn non_empty(value: String) -> Option<String> {
+    Some(value.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+impl Config {
+    // Reads HAC_CONFIG (a TOML file) when set, then lets HAC_* variables override it.
+    pub fn load() -> Result<Self, String> {
+        Self::from_sources(|name| std::env::var(name).ok())
+    }
+
+    pub fn from_sources(env: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let mut config = match env("HAC_CONFIG").and_then(non_empty) {
+            Some(path) => Self::from_file(&path)?,
+            None => Self::default(),
+        };
+
+        let mut errors = Vec::new();
+
+        for (var, unit, field) in [
+            ("HAC_LOGIN_TTL", "seconds", &mut config.login_ttl_secs),
+            ("HAC_PAGE_TTL", "seconds", &mut config.page_ttl_secs),
+            ("HAC_CLOSED_PAGE_TTL", "seconds", &mut config.closed_page_ttl_secs),
+            ("HAC_CLEANER_INTERVAL", "seconds", &mut config.cleaner_interval_secs),
+            ("HAC_WEBHOOK_INTERVAL", "seconds", &mut config.webhook_interval_secs),
+            ("HAC_WEBHOOK_TTL", "seconds", &mut config.webhook_ttl_secs),
+            ("HAC_WEBHOOK_ATTEMPTS", "attempts", &mut config.webhook_attempts),
+            ("HAC_WEBHOOK_BACKOFF", "seconds", &mut config.webhook_backoff_secs),
+            ("HAC_CONNECT_TIMEOUT", "seconds", &mut config.connect_timeout_secs),
+            ("HAC_READ_TIMEOUT", "seconds", &mut config.read_timeout_secs),
+            ("HAC_REQUEST_TIMEOUT", "seconds", &mut config.request_timeout_secs),
+            ("HAC_RETRY_ATTEMPTS", "attempts", &mut config.retry_attempts),
+            ("HAC_RETRY_DELAY_MS", "milliseconds", &mut config.retry_base_delay_ms),
+            ("HAC_BREAKER_THRESHOLD", "failures", &mut config.breaker_threshold),
+            ("HAC_BREAKER_COOLDOWN", "seconds", &mut config.breaker_cooldown_secs),
+        ] {
+            if let Some(value) = env(var) {
+                match parse_number(var, unit, &value) {
+                    Ok(v) => *field = v,
+                    Err(e) => errors.push(e),
+                }
+            }
+        }
+
+        if let Some(value) = env("HAC_WEBHOOK_ALLOW_PRIVATE") {
+            match value.trim().to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" => config.webhook_allow_private_urls = true,
+                "false" | "0" | "no" => config.webhook_allow_private_urls = false,
+                _ => errors.push(format!("HAC_WEBHOOK_ALLOW_PRIVATE: expected true or false, got '{}'", value)),
+            }
+        }
+
+        if let Some(bind) = env("HAC_BIND") {
+            config.bind = bind.trim().to_string();
+        }
+        if let Some(level) = env("HAC_LOG_LEVEL") {
+            config.log_level = level.trim().to_ascii_lowercase();
+        }
+        for (var, field) in [
+            ("HAC_TLS_CERT", &mut config.tls_cert),
+            ("HAC_TLS_KEY", &mut config.tls_key),
+            ("HAC_DEFAULT_LINK", &mut config.default_link),
+            ("HAC_DISTRICTS_FILE", &mut config.districts_file),
+            ("HAC_GPA_RULES_FILE", &mut config.gpa_rules_file),
+            ("HAC_GRADUATION_PLAN_FILE", &mut config.graduation_plan_file),
+        ] {
+            if let Some(value) = env(var) {
+                *field = non_empty(value);
+            }
+        }
+        if let Some(ids) = env("HAC_ALLOWED_DISTRICTS") {
+            config.allowed_districts = ids.split(',').filter_map(|id| non_empty(id.to_string())).collect();
+        }
+
+        errors.extend(config.validate());
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(format!("Invalid configuration:\n  - {}", errors.join("\n  - ")))
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read config file {}: {}", path, e))?;
+        toml::from_str(&text).map_err(|e| format!("Invalid config file {}: {}", path, e))
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.bind.parse::<SocketAddr>().is_err() {
+            errors.push(format!("bind: '{}' is not an address like 0.0.0.0:3000", self.bind));
+        }
+
+        match (&self.tls_cert, &self.tls_key) {
+            (Some(_), None) | (None, Some(_)) => errors.push("tls_cert and tls_key must be set together".to_string()),
+            (Some(cert), Some(key)) => {
+                for (name, path) in [("tls_cert", cert), ("tls_key", key)] {
+                    if !Path::new(path).is_file() {
+                        errors.push(format!("{}: {} does not exist", name, path));
+                    }
+                }
+            }
+            (None, None) => {}
+        }
+
+        for (name, value) in [
+            ("login_ttl_secs", self.login_ttl_secs),
+            ("page_ttl_secs", self.page_ttl_secs),
+            ("closed_page_ttl_secs", self.closed_page_ttl_secs),
+            ("cleaner_interval_secs", self.cleaner_interval_secs),
+            ("webhook_interval_secs", self.webhook_interval_secs),
+            ("webhook_ttl_secs", self.webhook_ttl_secs),
+            ("webhook_attempts", self.webhook_attempts),
+            ("connect_timeout_secs", self.connect_timeout_secs),
+            ("read_timeout_secs", self.read_timeout_secs),
+            ("request_timeout_secs", self.request_timeout_secs),
+            ("retry_attempts", self.retry_attempts),
+            ("breaker_threshold", self.breaker_threshold),
+            ("breaker_cooldown_secs", self.breaker_cooldown_secs),
+        ] {
+            if value == 0 {
+                errors.push(format!("{}: must be greater than 0", name));
+            }
+        }
+
+        for (name, value) in [("connect_timeout_secs", self.connect_timeout_secs), ("read_timeout_secs", self.read_timeout_secs)] {
+            if value > self.request_timeout_secs {
+                errors.push(format!(
+                    "{} ({}) must not exceed request_timeout_secs ({})",
+                    name, value, self.request_timeout_secs
+                ));
+            }
+        }
+
+        for (name, value) in [("retry_attempts", self.retry_attempts), ("webhook_attempts", self.webhook_attempts)] {
+            if value > 10 {
+                errors.push(format!("{}: {} is more than 10", name, value));
+            }
+        }
+
+        if let Some(link) = &self.default_link {
+            match reqwest::Url::parse(link) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                _ => errors.push(format!("default_link: '{}' is not an http(s) URL", link)),
+            }
+        }
+
+        if !LOG_LEVELS.contains(&self.log_level.as_str()) {
+            errors.push(format!("log_level: '{}' is not one of {}", self.log_level, LOG_LEVELS.join(", ")));
+        }
+
+        errors
+    }
+
+    pub fn webhook_settings(&self) -> webhooks::Settings {
+        webhooks::Settings {
+            attempts: u32::try_from(self.webhook_attempts).unwrap_or(u32::MAX),
+            backoff: Duration::from_secs(self.webhook_backoff_secs),
+            subscription_ttl: Duration::from_secs(self.webhook_ttl_secs),
+            allow_private_urls: self.webhook_allow_private_urls,
+        }
+    }
+
+    pub fn upstream_settings(&self) -> upstream::Settings {
+        upstream::Settings {
+            connect_timeout: Duration::from_secs(self.connect_timeout_secs),
+            read_timeout: Duration::from_secs(self.read_timeout_secs),
+            request_timeout: Duration::from_secs(self.request_timeout_secs),
+            retry_attempts: u32::try_from(self.retry_attempts).unwrap_or(u32::MAX),
+            retry_base_delay: Duration::from_millis(self.retry_base_delay_ms),
+            breaker_threshold: u32::try_from(self.breaker_threshold).unwrap_or(u32::MAX),
+            breaker_cooldown: Duration::from_secs(self.breaker_cooldown_secs),
+        }
+    }
+}
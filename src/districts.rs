@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct District {
+    pub id: String,
+    pub name: String,
+    pub base_url: String,
+    #[serde(default)]
+    pub database: Option<String>,
+    #[serde(default)]
+    pub login_fields: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DistrictRegistry {
+    default: String,
+    districts: Vec<District>,
+    #[serde(skip, default = "allow_custom_links")]
+    custom_links: bool,
+}
+
+fn allow_custom_links() -> bool {
+    true
+}
+
+impl DistrictRegistry {
+    pub fn builtin() -> Self {
+        Self::parse(include_str!("../districts.json")).expect("built-in districts.json is invalid")
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read district registry {}: {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("Invalid district registry {}: {}", path, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut registry: DistrictRegistry = serde_json::from_str(text).map_err(|e| e.to_string())?;
+
+        for district in &mut registry.districts {
+            district.base_url = district.base_url.trim_end_matches('/').to_string();
+        }
+
+        if registry.get(&registry.default).is_none() {
+            return Err(format!("default district '{}' is not in the registry", registry.default));
+        }
+
+        Ok(registry)
+    }
+
+    pub fn all(&self) -> &[District] {
+        &self.districts
+    }
+
+    pub fn get(&self, id: &str) -> Option<&District> {
+        self.districts.iter().find(|d| d.id.eq_ignore_ascii_case(id))
+    }
+
+    pub fn default_district(&self) -> &District {
+        self.get(&self.default).expect("default district is validated on load")
+    }
+
+    // Points the default at a registry district with this base URL, or at a custom district for it.
+    pub fn set_default_link(&mut self, link: &str) {
+        let district = self.for_link(link);
+        if district.id == "custom" {
+            self.districts.retain(|d| d.id != "custom");
+            self.districts.push(district.clone());
+        }
+        self.default = district.id;
+    }
+
+    // Keeps only the listed district ids; "custom" in the list keeps arbitrary links working.
+    pub fn restrict(&mut self, allowed: &[String]) -> Result<(), String> {
+        if allowed.is_empty() {
+            return Ok(());
+        }
+
+        let unknown: Vec<&str> = allowed
+            .iter()
+            .filter(|id| !id.eq_ignore_ascii_case("custom") && self.get(id).is_none())
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            return Err(format!("allowed_districts: unknown district ids {}", unknown.join(", ")));
+        }
+
+        let allows = |id: &str| allowed.iter().any(|a| a.eq_ignore_ascii_case(id));
+        self.custom_links = allows("custom");
+        self.districts.retain(|d| allows(&d.id));
+
+        if self.get(&self.default).is_none() {
+            return Err(format!("default district '{}' is not in allowed_districts", self.default));
+        }
+        Ok(())
+    }
+
+    pub fn allows(&self, district: &District) -> bool {
+        self.custom_links || self.districts.contains(district)
+    }
+
+    pub fn for_link(&self, link: &str) -> District {
+        let link = link.trim_end_matches('/');
+        if let Some(district) = self.districts.iter().find(|d| d.base_url.eq_ignore_ascii_case(link)) {
+            return district.clone();
+        }
+
+        District {
+            id: "custom".to_string(),
+            name: link.to_string(),
+            base_url: link.to_string(),
+            database: None,
+            login_fields: BTreeMap::new(),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use axum::{
+    extract::rejection::{JsonRejection, QueryRejection},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    InvalidCredentials,
+    SessionExpired,
+    UpstreamUnavailable(String),
+    UpstreamTimeout(String),
+    CircuitOpen { upstream: String, retry_after: u64 },
+    ParseFailure { page: String },
+    RateLimited,
+    BadRequest(String),
+    NotFound(String),
+    Internal(String),
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::InvalidCredentials => "invalid_credentials",
+            ApiError::SessionExpired => "session_expired",
+            ApiError::UpstreamUnavailable(_) => "upstream_unavailable",
+            ApiError::UpstreamTimeout(_) => "upstream_timeout",
+            ApiError::CircuitOpen { .. } => "upstream_circuit_open",
+            ApiError::ParseFailure { .. } => "parse_failure",
+            ApiError::RateLimited => "rate_limited",
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::InvalidCredentials | ApiError::SessionExpired => StatusCode::UNAUTHORIZED,
+            ApiError::UpstreamUnavailable(_) => StatusCode::BAD_GATEWAY,
+            ApiError::UpstreamTimeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            ApiError::CircuitOpen { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::ParseFailure { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn upstream(context: &str, err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            ApiError::UpstreamTimeout(format!("{}: {}", context, err))
+        } else {
+            ApiError::UpstreamUnavailable(format!("{}: {}", context, err))
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::InvalidCredentials => write!(f, "Invalid username or password"),
+            ApiError::SessionExpired => write!(f, "Session expired, please log in again"),
+            ApiError::UpstreamUnavailable(msg) => write!(f, "{}", msg),
+            ApiError::UpstreamTimeout(msg) => write!(f, "{}", msg),
+            ApiError::CircuitOpen { upstream, retry_after } => write!(
+                f,
+                "Home Access Center at {} keeps failing, not contacting it for {}s",
+                upstream, retry_after
+            ),
+            ApiError::ParseFailure { page } => write!(f, "Failed to parse {} page", page),
+            ApiError::RateLimited => write!(f, "Home Access Center is rate limiting requests"),
+            ApiError::BadRequest(msg) => write!(f, "{}", msg),
+            ApiError::NotFound(msg) => write!(f, "{}", msg),
+            ApiError::Internal(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::BadRequest(rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::BadRequest(rejection.body_text())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut body = json!({
+            "error": self.to_string(),
+            "code": self.code(),
+        });
+
+        if let ApiError::ParseFailure { page } = &self {
+            body["page"] = json!(page);
+        }
+
+        if let ApiError::CircuitOpen { upstream, retry_after } = &self {
+            body["upstream"] = json!(upstream);
+            body["retry_after"] = json!(retry_after);
+            return (self.status(), [(header::RETRY_AFTER, retry_after.to_string())], Json(body)).into_response();
+        }
+
+        (self.status(), Json(body)).into_response()
+    }
+}
@@ -1,51 +1,94 @@
 use reqwest::Client;
 use chrono::Datelike;
+use chrono::NaiveDate;
 use chrono::Utc;
 use scraper::{Html, Selector};
 use std::collections::HashMap;
 use crate::cache::Cache;
+use crate::error::ApiError;
+use crate::upstream;
+use crate::scraping::{calendar_day_number, extract_attendance_month, extract_calendar_target, extract_dropdown};
+
+pub(crate) fn check_status(response: reqwest::Response) -> Result<reqwest::Response, ApiError> {
+    let status = response.status();
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        Err(ApiError::RateLimited)
+    } else if status.is_server_error() {
+        Err(ApiError::UpstreamUnavailable(format!(
+            "Home Access Center returned {} for {}",
+            status,
+            response.url().path()
+        )))
+    } else {
+        Ok(response)
+    }
+}
 
-async fn fetch_page(
-    client: &Client,
-    base_url: &str,
-    endpoint: &str,
-) -> Result<String, String> {
-    let url = format!("{}/HomeAccess/Content/Student/{}", base_url, endpoint);
+fn is_login_page(body: &str) -> bool {
+    body.contains("LogOnDetails.UserName") && body.contains("__RequestVerificationToken")
+}
 
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|_| format!("Failed to fetch {} page", endpoint))?;
+async fn read_page(response: reqwest::Response, context: &str) -> Result<String, ApiError> {
+    let response = check_status(response)?;
+    let redirected_to_login = response.url().path().contains("/HomeAccess/Account/LogOn");
 
     let body = response
         .text()
         .await
-        .map_err(|_| format!("Failed to read {} page body", endpoint))?;
+        .map_err(|e| ApiError::upstream(context, e))?;
+
+    if redirected_to_login || is_login_page(&body) {
+        return Err(ApiError::SessionExpired);
+    }
 
     Ok(body)
 }
 
+// Concurrent requests for the same page share one upstream fetch through the cache; no_cache
+// requests always go to HAC themselves.
+async fn cached<F, Fut>(
+    cache: &Cache,
+    username: &str,
+    base_url: &str,
+    no_cache: bool,
+    endpoint: &str,
+    params: &str,
+    fetch: F,
+) -> Result<String, ApiError>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<String, ApiError>>,
+{
+    if no_cache {
+        fetch().await
+    } else {
+        cache.get_or_fetch_page(username, base_url, endpoint, params, fetch).await
+    }
+}
+
+async fn fetch_page(
+    client: &Client,
+    base_url: &str,
+    endpoint: &str,
+) -> Result<String, ApiError> {
+    let url = format!("{}/HomeAccess/Content/Student/{}", base_url, endpoint);
+
+    let response = upstream::send(client.get(&url), &format!("Failed to fetch {} page", endpoint)).await?;
+
+    read_page(response, &format!("Failed to read {} page body", endpoint)).await
+}
+
 pub async fn fetch_info_page(
     client: &Client,
     base_url: &str,
     cache: &Cache,
     username: &str,
     no_cache: bool,
-) -> Result<String, String> {
-    if !no_cache {
-        if let Some(cached) = cache.get_page(username, base_url, "Registration.aspx", "").await {
-            return Ok(cached);
-        }
-    }
-
-    let html = fetch_page(client, base_url, "Registration.aspx").await?;
-    
-    if !no_cache {
-        cache.set_page(username, base_url, "Registration.aspx", "", html.clone()).await;
-    }
-    
-    Ok(html)
+) -> Result<String, ApiError> {
+    cached(cache, username, base_url, no_cache, "Registration.aspx", "", || {
+        fetch_page(client, base_url, "Registration.aspx")
+    })
+    .await
 }
 
 pub async fn fetch_assignments_page(
@@ -54,20 +97,11 @@ pub async fn fetch_assignments_page(
     cache: &Cache,
     username: &str,
     no_cache: bool,
-) -> Result<String, String> {
-    if !no_cache {
-        if let Some(cached) = cache.get_page(username, base_url, "Assignments.aspx", "current").await {
-            return Ok(cached);
-        }
-    }
-
-    let html = fetch_page(client, base_url, "Assignments.aspx").await?;
-    
-    if !no_cache {
-        cache.set_page(username, base_url, "Assignments.aspx", "current", html.clone()).await;
-    }
-    
-    Ok(html)
+) -> Result<String, ApiError> {
+    cached(cache, username, base_url, no_cache, "Assignments.aspx", "current", || {
+        fetch_page(client, base_url, "Assignments.aspx")
+    })
+    .await
 }
 
 pub async fn fetch_report_page(
@@ -76,20 +110,11 @@ pub async fn fetch_report_page(
     cache: &Cache,
     username: &str,
     no_cache: bool,
-) -> Result<String, String> {
-    if !no_cache {
-        if let Some(cached) = cache.get_page(username, base_url, "ReportCards.aspx", "").await {
-            return Ok(cached);
-        }
-    }
-
-    let html = fetch_page(client, base_url, "ReportCards.aspx").await?;
-    
-    if !no_cache {
-        cache.set_page(username, base_url, "ReportCards.aspx", "", html.clone()).await;
-    }
-    
-    Ok(html)
+) -> Result<String, ApiError> {
+    cached(cache, username, base_url, no_cache, "ReportCards.aspx", "", || {
+        fetch_page(client, base_url, "ReportCards.aspx")
+    })
+    .await
 }
 
 pub async fn fetch_progress_page(
@@ -98,20 +123,11 @@ pub async fn fetch_progress_page(
     cache: &Cache,
     username: &str,
     no_cache: bool,
-) -> Result<String, String> {
-    if !no_cache {
-        if let Some(cached) = cache.get_page(username, base_url, "InterimProgress.aspx", "").await {
-            return Ok(cached);
-        }
-    }
-
-    let html = fetch_page(client, base_url, "InterimProgress.aspx").await?;
-    
-    if !no_cache {
-        cache.set_page(username, base_url, "InterimProgress.aspx", "", html.clone()).await;
-    }
-    
-    Ok(html)
+) -> Result<String, ApiError> {
+    cached(cache, username, base_url, no_cache, "InterimProgress.aspx", "", || {
+        fetch_page(client, base_url, "InterimProgress.aspx")
+    })
+    .await
 }
 
 pub async fn fetch_transcript_page(
@@ -120,20 +136,24 @@ pub async fn fetch_transcript_page(
     cache: &Cache,
     username: &str,
     no_cache: bool,
-) -> Result<String, String> {
-    if !no_cache {
-        if let Some(cached) = cache.get_page(username, base_url, "Transcript.aspx", "").await {
-            return Ok(cached);
-        }
-    }
+) -> Result<String, ApiError> {
+    cached(cache, username, base_url, no_cache, "Transcript.aspx", "", || {
+        fetch_page(client, base_url, "Transcript.aspx")
+    })
+    .await
+}
 
-    let html = fetch_page(client, base_url, "Transcript.aspx").await?;
-    
-    if !no_cache {
-        cache.set_page(username, base_url, "Transcript.aspx", "", html.clone()).await;
-    }
-    
-    Ok(html)
+pub async fn fetch_schedule_page(
+    client: &Client,
+    base_url: &str,
+    cache: &Cache,
+    username: &str,
+    no_cache: bool,
+) -> Result<String, ApiError> {
+    cached(cache, username, base_url, no_cache, "Classes.aspx", "", || {
+        fetch_page(client, base_url, "Classes.aspx")
+    })
+    .await
 }
 
 pub async fn fetch_name_page(
@@ -142,31 +162,15 @@ pub async fn fetch_name_page(
     cache: &Cache,
     username: &str,
     no_cache: bool,
-) -> Result<String, String> {
-    if !no_cache {
-        if let Some(cached) = cache.get_page(username, base_url, "Classwork", "").await {
-            return Ok(cached);
-        }
-    }
-
-    let url = format!("{}/HomeAccess/Classes/Classwork", base_url);
+) -> Result<String, ApiError> {
+    cached(cache, username, base_url, no_cache, "Classwork", "", || async {
+        let url = format!("{}/HomeAccess/Classes/Classwork", base_url);
 
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|_| "Failed to fetch classwork page".to_string())?;
+        let response = upstream::send(client.get(&url), "Failed to fetch classwork page").await?;
 
-    let html = response
-        .text()
-        .await
-        .map_err(|_| "Failed to read classwork page body".to_string())?;
-
-    if !no_cache {
-        cache.set_page(username, base_url, "Classwork", "", html.clone()).await;
-    }
-
-    Ok(html)
+        read_page(response, "Failed to read classwork page body").await
+    })
+    .await
 }
 
 fn format_six_weeks_param(input: &str) -> String {
@@ -190,40 +194,44 @@ fn format_six_weeks_param(input: &str) -> String {
 }
 
 
+// Runs before the one HAC selects by default have closed, so their grades won't change anymore.
+fn is_closed_run(html: &str, run: &str) -> bool {
+    let run_number = |value: &str| -> Option<u32> { value.split('-').next()?.parse().ok() };
+    let current = extract_dropdown(html, "ddlReportCardRuns")
+        .and_then(|(_, options)| options.into_iter().find(|o| o.selected))
+        .and_then(|o| run_number(&o.value));
+
+    matches!((run_number(run), current), (Some(run), Some(current)) if run < current)
+}
+
 pub async fn fetch_assignments_page_for_six_weeks(
     client: &Client,
     base_url: &str,
+    cache: &Cache,
+    username: &str,
+    no_cache: bool,
     six_weeks: &str,
-) -> Result<String, String> {
-    let assignments_url = format!("{}/HomeAccess/Content/Student/Assignments.aspx", base_url);
+) -> Result<String, ApiError> {
     let adjusted_six_weeks = format_six_weeks_param(six_weeks);
 
-    let response = client
-        .get(&assignments_url)
-        .send()
-        .await
-        .map_err(|_| "Failed to fetch assignments page".to_string())?;
+    // The form state has to come from this client's own HAC session, so the default page is
+    // fetched fresh here rather than taken from the cache.
+    let post = || async {
+        let assignments_url = format!("{}/HomeAccess/Content/Student/Assignments.aspx", base_url);
+        let body = fetch_page(client, base_url, "Assignments.aspx").await?;
+        let payload = extract_form_data(&body, &adjusted_six_weeks);
 
-    let body = response
-        .text()
-        .await
-        .map_err(|_| "Failed to read assignments page".to_string())?;
+        let post_resp = upstream::send(client.post(&assignments_url).form(&payload), "Failed to post assignments request").await?;
 
-    let payload = extract_form_data(&body, &adjusted_six_weeks);
-
-    let post_resp = client
-        .post(&assignments_url)
-        .form(&payload)
-        .send()
-        .await
-        .map_err(|_| "Failed to post assignments request".to_string())?;
-
-    let post_body = post_resp
-        .text()
-        .await
-        .map_err(|_| "Failed to read assignments response".to_string())?;
+        let html = read_page(post_resp, "Failed to read assignments response").await?;
+        Ok((html, is_closed_run(&body, &adjusted_six_weeks)))
+    };
 
-    Ok(post_body)
+    if no_cache {
+        post().await.map(|(html, _)| html)
+    } else {
+        cache.get_or_fetch_run_page(username, base_url, "Assignments.aspx", &adjusted_six_weeks, post).await
+    }
 }
 
 fn extract_form_data(body: &str, adjusted_six_weeks: &str) -> HashMap<&'static str, String> {
@@ -278,3 +286,193 @@ fn extract_form_data(body: &str, adjusted_six_weeks: &str) -> HashMap<&'static s
 
     form_data
 }
+
+fn postback_form(body: &str) -> Vec<(String, String)> {
+    let document = Html::parse_document(body);
+    let input_selector = Selector::parse("input[type='hidden'][name]").unwrap();
+    let select_selector = Selector::parse("select[name]").unwrap();
+    let option_selector = Selector::parse("option[selected]").unwrap();
+
+    let mut fields: Vec<(String, String)> = document
+        .select(&input_selector)
+        .map(|input| {
+            let el = input.value();
+            (el.attr("name").unwrap_or_default().to_string(), el.attr("value").unwrap_or_default().to_string())
+        })
+        .collect();
+
+    for select in document.select(&select_selector) {
+        if let Some(option) = select.select(&option_selector).next() {
+            fields.push((
+                select.value().attr("name").unwrap_or_default().to_string(),
+                option.value().attr("value").unwrap_or_default().to_string(),
+            ));
+        }
+    }
+
+    fields
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn fetch_dropdown_selection(
+    client: &Client,
+    base_url: &str,
+    cache: &Cache,
+    username: &str,
+    no_cache: bool,
+    endpoint: &str,
+    body: &str,
+    dropdown: &str,
+    choice: &str,
+) -> Result<String, ApiError> {
+    let (select_name, options) = extract_dropdown(body, dropdown)
+        .ok_or_else(|| ApiError::ParseFailure { page: endpoint.to_string() })?;
+
+    let option = options
+        .iter()
+        .find(|o| o.value.eq_ignore_ascii_case(choice.trim()) || o.label.eq_ignore_ascii_case(choice.trim()))
+        .ok_or_else(|| {
+            let available: Vec<&str> = options.iter().map(|o| o.label.as_str()).collect();
+            ApiError::BadRequest(format!("Unknown selection '{}', available: {}", choice, available.join(", ")))
+        })?;
+
+    if option.selected {
+        return Ok(body.to_string());
+    }
+
+    // The page passed in may be cached from another HAC session, so the form state posted back
+    // comes from a fresh load on this client.
+    cached(cache, username, base_url, no_cache, endpoint, &option.value, || async {
+        let page = fetch_page(client, base_url, endpoint).await?;
+        let mut form = postback_form(&page);
+        form.retain(|(name, _)| name != "__EVENTTARGET" && name != "__EVENTARGUMENT" && name != &select_name);
+        form.push(("__EVENTTARGET".to_string(), select_name.clone()));
+        form.push(("__EVENTARGUMENT".to_string(), String::new()));
+        form.push((select_name.clone(), option.value.clone()));
+
+        let url = format!("{}/HomeAccess/Content/Student/{}", base_url, endpoint);
+        let response = upstream::send(client.post(&url).form(&form), &format!("Failed to post {} selection", endpoint)).await?;
+
+        read_page(response, &format!("Failed to read {} selection", endpoint)).await
+    })
+    .await
+}
+
+pub async fn fetch_report_page_for_run(
+    client: &Client,
+    base_url: &str,
+    cache: &Cache,
+    username: &str,
+    no_cache: bool,
+    run: Option<&str>,
+) -> Result<String, ApiError> {
+    let html = fetch_report_page(client, base_url, cache, username, no_cache).await?;
+
+    match run {
+        Some(run) => {
+            fetch_dropdown_selection(client, base_url, cache, username, no_cache, "ReportCards.aspx", &html, "ddlRCRuns", run)
+                .await
+        }
+        None => Ok(html),
+    }
+}
+
+pub async fn fetch_progress_page_for_date(
+    client: &Client,
+    base_url: &str,
+    cache: &Cache,
+    username: &str,
+    no_cache: bool,
+    date: Option<&str>,
+) -> Result<String, ApiError> {
+    let html = fetch_progress_page(client, base_url, cache, username, no_cache).await?;
+
+    match date {
+        Some(date) => {
+            fetch_dropdown_selection(client, base_url, cache, username, no_cache, "InterimProgress.aspx", &html, "ddlIPRDates", date)
+                .await
+        }
+        None => Ok(html),
+    }
+}
+
+const ATTENDANCE_PAGE: &str = "MonthlyView.aspx";
+
+async fn fetch_attendance_page(
+    client: &Client,
+    base_url: &str,
+    cache: &Cache,
+    username: &str,
+    no_cache: bool,
+) -> Result<String, ApiError> {
+    cached(cache, username, base_url, no_cache, ATTENDANCE_PAGE, "", || load_attendance_page(client, base_url)).await
+}
+
+async fn load_attendance_page(client: &Client, base_url: &str) -> Result<String, ApiError> {
+    let url = format!("{}/HomeAccess/Content/Attendance/{}", base_url, ATTENDANCE_PAGE);
+
+    let response = upstream::send(client.get(&url), "Failed to fetch attendance page").await?;
+
+    read_page(response, "Failed to read attendance page body").await
+}
+
+pub async fn fetch_attendance_months(
+    client: &Client,
+    base_url: &str,
+    cache: &Cache,
+    username: &str,
+    no_cache: bool,
+    months: &[NaiveDate],
+) -> Result<Vec<String>, ApiError> {
+    let parse_failure = || ApiError::ParseFailure { page: ATTENDANCE_PAGE.to_string() };
+    let url = format!("{}/HomeAccess/Content/Attendance/{}", base_url, ATTENDANCE_PAGE);
+
+    let start = fetch_attendance_page(client, base_url, cache, username, no_cache).await?;
+    let target = extract_calendar_target(&start).ok_or_else(parse_failure)?;
+    let first = extract_attendance_month(&start).ok_or_else(parse_failure)?;
+    let mut seen = vec![(first.year, first.month, start)];
+    let mut pages = Vec::new();
+    // Postbacks chain from the last page this client loaded. The starting page may be cached from
+    // another HAC session, so the first postback loads the calendar fresh for its form state.
+    let mut current: Option<String> = None;
+
+    for first_of_month in months {
+        if let Some((_, _, html)) = seen
+            .iter()
+            .find(|(year, month, _)| *year == first_of_month.year() && *month == first_of_month.month()) {
+            pages.push(html.clone());
+            continue;
+        }
+
+        let key = first_of_month.format("%Y-%m").to_string();
+
+        if !no_cache
+            && let Some(cached) = cache.get_page(username, base_url, ATTENDANCE_PAGE, &key).await {
+            pages.push(cached);
+            continue;
+        }
+
+        let previous = match current.take() {
+            Some(page) => page,
+            None => load_attendance_page(client, base_url).await?,
+        };
+        let mut form = postback_form(&previous);
+        form.retain(|(name, _)| name != "__EVENTTARGET" && name != "__EVENTARGUMENT");
+        form.push(("__EVENTTARGET".to_string(), target.clone()));
+        form.push(("__EVENTARGUMENT".to_string(), format!("V{}", calendar_day_number(*first_of_month))));
+
+        let response = upstream::send(client.post(&url).form(&form), "Failed to post attendance month").await?;
+
+        let html = read_page(response, "Failed to read attendance month").await?;
+
+        if !no_cache {
+            cache.set_page(username, base_url, ATTENDANCE_PAGE, &key, html.clone()).await;
+        }
+
+        seen.push((first_of_month.year(), first_of_month.month(), html.clone()));
+        pages.push(html.clone());
+        current = Some(html);
+    }
+
+    Ok(pages)
+}
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use crate::grades::Grade;
+use crate::scraping::{extract_averages_typed, shorten_class_name};
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GpaRules {
+    pub levels: Vec<Level>,
+    pub bands: Vec<Band>,
+    #[serde(default = "default_level")]
+    pub default_level: String,
+    #[serde(default = "default_passing")]
+    pub passing: f64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Level {
+    pub name: String,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub code_prefixes: Vec<String>,
+    pub bonus: f64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Band {
+    pub min: f64,
+    pub points: f64,
+}
+
+fn default_level() -> String {
+    "Regular".to_string()
+}
+
+fn default_passing() -> f64 {
+    70.0
+}
+
+impl Default for GpaRules {
+    fn default() -> Self {
+        let level = |name: &str, keywords: &[&str], bonus: f64| Level {
+            name: name.to_string(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            code_prefixes: Vec::new(),
+            bonus,
+        };
+
+        Self {
+            levels: vec![
+                level("IB", &["IB"], 1.0),
+                level("AP", &["AP"], 1.0),
+                level("Dual Credit", &["DC", "DUAL"], 1.0),
+                level("Advanced", &["ADV", "GT", "HON", "HONORS", "PAP", "PREAP", "KAP"], 0.5),
+            ],
+            bands: vec![
+                Band { min: 90.0, points: 4.0 },
+                Band { min: 80.0, points: 3.0 },
+                Band { min: 70.0, points: 2.0 },
+            ],
+            default_level: default_level(),
+            passing: default_passing(),
+        }
+    }
+}
+
+impl GpaRules {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read GPA rules {}: {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("Invalid GPA rules {}: {}", path, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let rules: GpaRules = serde_json::from_str(text).map_err(|e| e.to_string())?;
+
+        if rules.bands.is_empty() {
+            return Err("bands must not be empty".to_string());
+        }
+        if let Some(band) = rules.bands.iter().find(|b| !(0.0..=100.0).contains(&b.min) || b.points < 0.0) {
+            return Err(format!("band min {} / points {} is out of range", band.min, band.points));
+        }
+        if !(0.0..=100.0).contains(&rules.passing) {
+            return Err(format!("passing {} is not between 0 and 100", rules.passing));
+        }
+        Ok(rules)
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ClassGpa {
+    pub class: String,
+    pub average: Grade,
+    pub level: String,
+    pub unweighted_points: Option<f64>,
+    pub weighted_points: Option<f64>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct GpaReport {
+    pub unweighted: Option<f64>,
+    pub weighted: Option<f64>,
+    pub classes: Vec<ClassGpa>,
+}
+
+impl GpaRules {
+    pub fn level_for(&self, class_name: &str) -> (&str, f64) {
+        let tokens: Vec<String> = class_name
+            .split(|c: char| !c.is_alphanumeric() && c != '-')
+            .map(|t| t.replace('-', "").to_uppercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+        let code = tokens.first().map(String::as_str).unwrap_or_default();
+
+        for level in &self.levels {
+            let keyword_hit = level
+                .keywords
+                .iter()
+                .any(|k| tokens.iter().any(|t| t == &k.replace('-', "").to_uppercase()));
+            let code_hit = level
+                .code_prefixes
+                .iter()
+                .any(|p| code.starts_with(&p.to_uppercase()));
+
+            if keyword_hit || code_hit {
+                return (&level.name, level.bonus);
+            }
+        }
+
+        (&self.default_level, 0.0)
+    }
+
+    pub fn base_points(&self, average: f64) -> f64 {
+        self.bands
+            .iter()
+            .filter(|b| average >= b.min)
+            .map(|b| b.points)
+            .fold(0.0, f64::max)
+    }
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        let avg = values.iter().sum::<f64>() / values.len() as f64;
+        Some((avg * 1000.0).round() / 1000.0)
+    }
+}
+
+pub fn project_gpa(rules: &GpaRules, html: &str, short: bool) -> GpaReport {
+    let averages = extract_averages_typed(html, false);
+
+    let mut classes: Vec<ClassGpa> = averages
+        .into_iter()
+        .map(|(name, average)| {
+            let (level, bonus) = rules.level_for(&name);
+            let unweighted = average.score.points().map(|avg| rules.base_points(avg));
+            let weighted = average.score.points().map(|avg| {
+                let base = rules.base_points(avg);
+                if avg >= rules.passing { base + bonus } else { base }
+            });
+
+            ClassGpa {
+                class: if short { shorten_class_name(&name) } else { name.clone() },
+                level: level.to_string(),
+                average,
+                unweighted_points: unweighted,
+                weighted_points: weighted,
+            }
+        })
+        .collect();
+
+    classes.sort_by(|a, b| a.class.cmp(&b.class));
+
+    let unweighted: Vec<f64> = classes.iter().filter_map(|c| c.unweighted_points).collect();
+    let weighted: Vec<f64> = classes.iter().filter_map(|c| c.weighted_points).collect();
+
+    GpaReport {
+        unweighted: mean(&unweighted),
+        weighted: mean(&weighted),
+        classes,
+    }
+}
@@ -0,0 +1,80 @@
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "status", content = "value", rename_all = "snake_case")]
+pub enum Score {
+    Points(f64),
+    Letter(String),
+    Exempt,
+    Missing,
+    Incomplete,
+    NotGraded,
+    Other(String),
+}
+
+impl Score {
+    pub fn parse(raw: &str) -> Score {
+        let text = raw.trim();
+        let number = text.trim_end_matches('%').trim();
+
+        if text.is_empty() {
+            return Score::NotGraded;
+        }
+
+        match text.to_ascii_uppercase().as_str() {
+            "X" | "EX" | "EXC" | "EXEMPT" => return Score::Exempt,
+            "M" | "MSG" | "MISSING" => return Score::Missing,
+            "I" | "INC" | "INCOMPLETE" => return Score::Incomplete,
+            "NG" | "N/A" | "NOT GRADED" => return Score::NotGraded,
+            _ => {}
+        }
+
+        // f64 parsing also takes "NaN" and "inf", which would poison every average they touch.
+        if let Ok(points) = number.parse::<f64>()
+            && points.is_finite() {
+            return Score::Points(points);
+        }
+
+        if is_letter_grade(text) {
+            return Score::Letter(text.to_ascii_uppercase());
+        }
+
+        Score::Other(text.to_string())
+    }
+
+    pub fn points(&self) -> Option<f64> {
+        match self {
+            Score::Points(p) => Some(*p),
+            _ => None,
+        }
+    }
+}
+
+fn is_letter_grade(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) if "ABCDFPSUN".contains(c.to_ascii_uppercase()) => {}
+        _ => return false,
+    }
+    matches!((chars.next(), chars.next()), (None, _) | (Some('+' | '-'), None))
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Grade {
+    pub raw: String,
+    #[serde(flatten)]
+    pub score: Score,
+}
+
+impl Grade {
+    pub fn parse(raw: &str) -> Grade {
+        Grade {
+            raw: raw.trim().to_string(),
+            score: Score::parse(raw),
+        }
+    }
+}
+
+pub fn parse_number(raw: &str) -> Option<f64> {
+    raw.trim().trim_end_matches('%').trim().replace(',', "").parse().ok().filter(|n: &f64| n.is_finite())
+}
@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+use crate::scraping::{extract_classes, extract_transcript_typed};
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GraduationPlan {
+    pub name: String,
+    pub requirements: Vec<Requirement>,
+    #[serde(default = "default_electives")]
+    pub electives: String,
+    #[serde(default = "default_in_progress_credit")]
+    pub in_progress_credit: f64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Requirement {
+    pub subject: String,
+    pub credits: f64,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub code_prefixes: Vec<String>,
+}
+
+fn default_electives() -> String {
+    "Electives".to_string()
+}
+
+fn default_in_progress_credit() -> f64 {
+    0.5
+}
+
+impl Default for GraduationPlan {
+    fn default() -> Self {
+        let requirement = |subject: &str, credits: f64, keywords: &[&str]| Requirement {
+            subject: subject.to_string(),
+            credits,
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            code_prefixes: Vec::new(),
+        };
+
+        Self {
+            name: "Texas Foundation with Endorsement".to_string(),
+            requirements: vec![
+                requirement("English", 4.0, &["ENGLISH", "ENG"]),
+                requirement("Mathematics", 4.0, &["ALGEBRA", "ALG", "GEOMETRY", "GEOM", "PRECALCULUS", "PRECAL", "CALCULUS", "CALC", "STATISTICS", "STAT", "MATH"]),
+                requirement("Science", 4.0, &["BIOLOGY", "BIO", "CHEMISTRY", "CHEM", "PHYSICS", "IPC", "SCIENCE", "ENVIRONMENTAL", "ANATOMY", "ASTRONOMY"]),
+                requirement("Social Studies", 3.0, &["HISTORY", "HIST", "GEOGRAPHY", "GEOG", "GOVERNMENT", "GOVT", "ECONOMICS", "ECON"]),
+                requirement("Languages Other Than English", 2.0, &["SPANISH", "FRENCH", "GERMAN", "LATIN", "CHINESE", "JAPANESE", "ASL"]),
+                requirement("Physical Education", 1.0, &["ATHLETICS", "PE", "FITNESS"]),
+                requirement("Fine Arts", 1.0, &["ART", "BAND", "CHOIR", "ORCHESTRA", "THEATRE", "THEATER", "MUSIC", "DANCE"]),
+                requirement("Electives", 7.0, &[]),
+            ],
+            electives: default_electives(),
+            in_progress_credit: default_in_progress_credit(),
+        }
+    }
+}
+
+impl GraduationPlan {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read graduation plan {}: {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("Invalid graduation plan {}: {}", path, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let plan: GraduationPlan = serde_json::from_str(text).map_err(|e| e.to_string())?;
+
+        if plan.requirements.is_empty() {
+            return Err("requirements must not be empty".to_string());
+        }
+        if let Some(requirement) = plan.requirements.iter().find(|r| r.credits <= 0.0) {
+            return Err(format!("{} needs more than 0 credits", requirement.subject));
+        }
+        if plan.in_progress_credit < 0.0 {
+            return Err(format!("in_progress_credit {} is negative", plan.in_progress_credit));
+        }
+        Ok(plan)
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct RequirementProgress {
+    pub subject: String,
+    pub required: f64,
+    pub completed: f64,
+    pub in_progress: f64,
+    pub remaining: f64,
+    pub courses: Vec<String>,
+    pub current_courses: Vec<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct GraduationAudit {
+    pub plan: String,
+    pub required: f64,
+    pub completed: f64,
+    pub in_progress: f64,
+    pub remaining: f64,
+    pub requirements: Vec<RequirementProgress>,
+}
+
+impl GraduationPlan {
+    pub fn subject_for(&self, code: &str, description: &str) -> &str {
+        let tokens: Vec<String> = description
+            .split(|c: char| !c.is_alphanumeric())
+            .map(|t| t.to_uppercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+        let code = code.trim().to_uppercase();
+
+        for requirement in &self.requirements {
+            let code_hit = requirement
+                .code_prefixes
+                .iter()
+                .any(|p| code.starts_with(&p.to_uppercase()));
+            let keyword_hit = requirement
+                .keywords
+                .iter()
+                .any(|k| tokens.iter().any(|t| t == &k.to_uppercase()));
+
+            if code_hit || keyword_hit {
+                return &requirement.subject;
+            }
+        }
+
+        &self.electives
+    }
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn split_class_name(name: &str) -> (&str, &str) {
+    let code = name.split_whitespace().next().unwrap_or_default();
+    let description = match name.split_once(" - ") {
+        Some((_, rest)) => rest.trim_start_matches(|c: char| c.is_ascii_digit() || c.is_whitespace()),
+        None => name,
+    };
+    (code, description)
+}
+
+// Credits past a subject's requirement count toward electives, as they do on a Texas transcript.
+fn allocate(progress: &mut [RequirementProgress], electives: &str, subject: &str, credits: f64, current: bool) {
+    let index = progress.iter().position(|p| p.subject == subject);
+    let elective_index = progress.iter().position(|p| p.subject == electives);
+
+    let Some(index) = index.or(elective_index) else {
+        return;
+    };
+
+    let slot = &mut progress[index];
+    let room = (slot.required - slot.completed - slot.in_progress).max(0.0);
+    let overflow = match elective_index {
+        Some(e) if e != index => (credits - room).max(0.0),
+        _ => 0.0,
+    };
+
+    let amount = credits - overflow;
+    if current { slot.in_progress += amount } else { slot.completed += amount }
+
+    if let Some(e) = elective_index.filter(|_| overflow > 0.0) {
+        let slot = &mut progress[e];
+        if current { slot.in_progress += overflow } else { slot.completed += overflow }
+    }
+}
+
+pub fn audit(plan: &GraduationPlan, transcript_html: &str, assignments_html: &str) -> GraduationAudit {
+    let transcript = extract_transcript_typed(transcript_html);
+
+    let mut progress: Vec<RequirementProgress> = plan
+        .requirements
+        .iter()
+        .map(|r| RequirementProgress {
+            subject: r.subject.clone(),
+            required: r.credits,
+            completed: 0.0,
+            in_progress: 0.0,
+            remaining: r.credits,
+            courses: Vec::new(),
+            current_courses: Vec::new(),
+        })
+        .collect();
+
+    for course in transcript.semesters.iter().flat_map(|s| &s.courses) {
+        let credit = match course.credit {
+            Some(c) if c > 0.0 => c,
+            _ => continue,
+        };
+
+        let subject = plan.subject_for(&course.code, &course.description);
+        allocate(&mut progress, &plan.electives, subject, credit, false);
+        if let Some(p) = progress.iter_mut().find(|p| p.subject == subject) {
+            p.courses.push(format!("{} {}", course.code, course.description));
+        }
+    }
+
+    for class in extract_classes(assignments_html, false) {
+        let (code, description) = split_class_name(&class);
+        let subject = plan.subject_for(code, description);
+        allocate(&mut progress, &plan.electives, subject, plan.in_progress_credit, true);
+        if let Some(p) = progress.iter_mut().find(|p| p.subject == subject) {
+            p.current_courses.push(class.clone());
+        }
+    }
+
+    for p in &mut progress {
+        p.completed = round2(p.completed);
+        p.in_progress = round2(p.in_progress);
+        p.remaining = round2((p.required - p.completed - p.in_progress).max(0.0));
+    }
+
+    GraduationAudit {
+        plan: plan.name.clone(),
+        required: round2(progress.iter().map(|p| p.required).sum()),
+        completed: round2(progress.iter().map(|p| p.completed).sum()),
+        in_progress: round2(progress.iter().map(|p| p.in_progress).sum()),
+        remaining: round2(progress.iter().map(|p| p.remaining).sum()),
+        requirements: progress,
+    }
+}
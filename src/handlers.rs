@@ -1,81 +1,277 @@
 use axum::{
-    extract::{Query, State},
-    http::StatusCode,
+    extract::{rejection::{JsonRejection, QueryRejection}, Path, Query, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
     response::Json,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     http::header,
 };
-use serde_json::json;
+use serde_json::{json, Value};
 use serde::Deserialize;
-use crate::auth::login_handler;
+use crate::auth::{login_handler, login_options};
+use std::sync::Arc;
 use crate::cache::Cache;
-use crate::scraping::{extract_assignments, extract_averages, extract_classes, extract_gradebook, extract_info, extract_name, extract_report_cards, extract_weightings, extract_progress, extract_transcript, extract_rank};
-use crate::fetchers::{fetch_info_page, fetch_assignments_page, fetch_name_page, fetch_assignments_page_for_six_weeks, fetch_report_page, fetch_progress_page, fetch_transcript_page};
+use crate::districts::{District, DistrictRegistry};
+use crate::error::ApiError;
+use crate::gpa::{project_gpa, GpaRules};
+use crate::graduation::{self, GraduationPlan};
+use crate::attendance;
+use crate::calendar;
+use crate::cache::Snapshot;
+use crate::changes::{self, ChangeReport};
+use crate::upstream;
+use crate::webhooks::Webhooks;
+use crate::calculator::{self, find_class, WhatIfRequest};
+use crate::scraping::{extract_assignments, extract_assignments_typed, extract_averages, extract_averages_typed, extract_classes, extract_gradebook, extract_gradebook_typed, extract_info, extract_name, extract_report_cards, extract_report_cards_typed, extract_report_card_runs, extract_weightings, extract_weightings_typed, extract_progress, extract_progress_typed, extract_progress_dates, extract_transcript, extract_transcript_typed, extract_schedule, extract_rank};
+use crate::fetchers::{fetch_info_page, fetch_assignments_page, fetch_name_page, fetch_assignments_page_for_six_weeks, fetch_report_page, fetch_report_page_for_run, fetch_progress_page, fetch_progress_page_for_date, fetch_transcript_page, fetch_schedule_page, fetch_attendance_months};
 
 #[derive(Deserialize)]
 pub struct LoginParams {
-    pub user: String,
-    pub pass: String,
+    pub user: Option<String>,
+    pub pass: Option<String>,
     pub link: Option<String>,
+    pub district: Option<String>,
     pub short: Option<bool>,
     pub six_weeks: Option<String>,
+    pub run: Option<String>,
+    pub date: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub since: Option<String>,
     pub no_cache: Option<bool>,
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct LoginRequest {
+    pub user: String,
+    pub pass: String,
+    pub link: Option<String>,
+    pub district: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SubscriptionRequest {
+    pub url: String,
+    pub secret: Option<String>,
+    pub short: Option<bool>,
+}
+
+pub(crate) struct Credentials {
+    pub(crate) username: String,
+    pub(crate) password: String,
+    pub(crate) district: District,
+}
+
+impl Credentials {
+    pub(crate) fn link(&self) -> &str {
+        &self.district.base_url
+    }
+}
+
+fn resolve_district(
+    districts: &DistrictRegistry,
+    district: Option<&str>,
+    link: Option<&str>,
+) -> Result<District, ApiError> {
+    match (district, link) {
+        (Some(id), _) => districts
+            .get(id)
+            .cloned()
+            .ok_or_else(|| ApiError::BadRequest(format!("Unknown district '{}', see /api/districts", id))),
+        (None, Some(link)) => {
+            let district = districts.for_link(link);
+            if districts.allows(&district) {
+                Ok(district)
+            } else {
+                Err(ApiError::BadRequest(format!("District link '{}' is not allowed, see /api/districts", link)))
+            }
+        }
+        (None, None) => Ok(districts.default_district().clone()),
+    }
+}
+
+const QUERY_CREDENTIALS_WARNING: &str =
+    "299 - \"user/pass query parameters are deprecated, log in via POST /api/login and send a Bearer token\"";
+
+// Passwords in the query string end up in proxy and access logs. They still work, but every such
+// response is marked deprecated so clients move to bearer tokens.
+pub async fn deprecate_query_credentials(request: Request, next: Next) -> Response {
+    let has_password = request
+        .uri()
+        .query()
+        .is_some_and(|query| query.split('&').any(|pair| pair.starts_with("pass=")));
+    let path = request.uri().path().to_string();
+
+    let mut response = next.run(request).await;
+    if has_password {
+        tracing::warn!("Deprecated user/pass query credentials used on {}", path);
+        let headers = response.headers_mut();
+        headers.insert("deprecation", HeaderValue::from_static("true"));
+        headers.insert(header::WARNING, HeaderValue::from_static(QUERY_CREDENTIALS_WARNING));
+    }
+    response
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    if scheme.eq_ignore_ascii_case("Bearer") && !token.trim().is_empty() {
+        Some(token.trim())
+    } else {
+        None
+    }
+}
+
+async fn resolve_credentials(
+    cache: &Cache,
+    districts: &DistrictRegistry,
+    headers: &HeaderMap,
+    params: &LoginParams,
+) -> Result<Credentials, ApiError> {
+    if let Some(token) = bearer_token(headers) {
+        let session = cache.get_session(token).await.ok_or(ApiError::SessionExpired)?;
+        return Ok(Credentials {
+            username: session.username,
+            password: session.password,
+            district: session.district,
+        });
+    }
+
+    match (&params.user, &params.pass) {
+        (Some(user), Some(pass)) => Ok(Credentials {
+            username: user.clone(),
+            password: pass.clone(),
+            district: resolve_district(districts, params.district.as_deref(), params.link.as_deref())?,
+        }),
+        _ => Err(ApiError::BadRequest(
+            "Missing credentials: log in via /api/login and send an Authorization: Bearer token".to_string(),
+        )),
+    }
+}
+
+fn wants_typed(format: Option<&str>) -> Result<bool, ApiError> {
+    match format {
+        None | Some("raw") => Ok(false),
+        Some("typed") => Ok(true),
+        Some(other) => Err(ApiError::BadRequest(format!(
+            "Unsupported format '{}', expected 'raw' or 'typed'",
+            other
+        ))),
+    }
+}
+
+fn raw_only(format: Option<&str>) -> Result<(), ApiError> {
+    match format {
+        None | Some("raw") => Ok(()),
+        Some(other) => Err(ApiError::BadRequest(format!(
+            "Unsupported format '{}', this endpoint only returns 'raw'",
+            other
+        ))),
+    }
 }
 
 async fn get_or_login(
     cache: &Cache,
-    username: &str,
-    password: &str,
-    url: &str,
+    creds: &Credentials,
     no_cache: bool,
-) -> Result<reqwest::Client, String> {
-    if !no_cache {
-        if let Some(client) = cache.get_client(username, url).await {
-            return Ok(client);
-        }
+) -> Result<reqwest::Client, ApiError> {
+    if no_cache {
+        return login_handler(&creds.username, &creds.password, &creds.district).await;
     }
 
-    let client = login_handler(username, password, url).await?;
-    
-    if !no_cache {
-        cache.set_client(username, url, client.clone()).await;
+    cache
+        .get_or_login(&creds.username, creds.link(), &creds.password, || {
+            login_handler(&creds.username, &creds.password, &creds.district)
+        })
+        .await
+}
+
+pub(crate) async fn with_relogin<T, F, Fut>(
+    cache: &Cache,
+    creds: &Credentials,
+    no_cache: bool,
+    fetch: F,
+) -> Result<T, ApiError>
+where
+    F: Fn(reqwest::Client) -> Fut,
+    Fut: Future<Output = Result<T, ApiError>>,
+{
+    let client = get_or_login(cache, creds, no_cache).await?;
+
+    match fetch(client).await {
+        Err(ApiError::SessionExpired) => {
+            cache.remove_client(&creds.username, creds.link()).await;
+            let client = get_or_login(cache, creds, no_cache).await?;
+
+            fetch(client).await.map_err(|e| match e {
+                ApiError::SessionExpired => ApiError::UpstreamUnavailable(
+                    "Home Access Center ended the session right after logging in".to_string(),
+                ),
+                other => other,
+            })
+        }
+        result => result,
     }
-    
-    Ok(client)
+}
+
+async fn load_assignments_page(
+    cache: &Cache,
+    districts: &DistrictRegistry,
+    headers: &HeaderMap,
+    params: &LoginParams,
+) -> Result<String, ApiError> {
+    let creds = resolve_credentials(cache, districts, headers, params).await?;
+    fetch_assignments_for(cache, &creds, params).await
+}
+
+async fn fetch_assignments_for(
+    cache: &Cache,
+    creds: &Credentials,
+    params: &LoginParams,
+) -> Result<String, ApiError> {
+    let no_cache = params.no_cache.unwrap_or(false);
+
+    with_relogin(cache, creds, no_cache, move |client| async move {
+        if let Some(ref six_weeks) = params.six_weeks {
+            fetch_assignments_page_for_six_weeks(&client, creds.link(), cache, &creds.username, no_cache, six_weeks).await
+        } else {
+            fetch_assignments_page(&client, creds.link(), cache, &creds.username, no_cache).await
+        }
+    })
+    .await
 }
 
 macro_rules! endpoint {
+    (@typed $params:ident) => {
+        raw_only($params.format.as_deref()).map(|()| false)
+    };
+
+    (@typed $params:ident, $typed_fn:path) => {
+        wants_typed($params.format.as_deref())
+    };
+
     (
         $name:ident,
         assignments_page_scraper: $extract_fn:path
+        $(, typed: $typed_fn:path)?
     ) => {
         pub async fn $name(
             State(cache): State<Cache>,
-            Query(params): Query<LoginParams>
-        ) -> impl IntoResponse {
-            let url = params.link.clone().unwrap_or_else(|| "https://homeaccess.katyisd.org".to_string());
-            let no_cache = params.no_cache.unwrap_or(false);
-
-            let client = match get_or_login(&cache, &params.user, &params.pass, &url, no_cache).await {
-                Ok(c) => c,
-                Err(err) if err == "Invalid username or password" => {
-                    return (StatusCode::UNAUTHORIZED, Json(json!({ "error": err })));
-                }
-                Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e }))),
-            };
-
-            let html = match if let Some(ref six_weeks) = params.six_weeks {
-                fetch_assignments_page_for_six_weeks(&client, &url, six_weeks).await
-            } else {
-                fetch_assignments_page(&client, &url, &cache, &params.user, no_cache).await
-            } {
-                Ok(body) => body,
-                Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e }))),
-            };
-
-            let data = $extract_fn(&html, params.short.unwrap_or(false));
+            State(districts): State<Arc<DistrictRegistry>>,
+            headers: HeaderMap,
+            params: Result<Query<LoginParams>, QueryRejection>
+        ) -> Result<Json<Value>, ApiError> {
+            let Query(params) = params?;
+            let typed = endpoint!(@typed params $(, $typed_fn)?)?;
+            let html = load_assignments_page(&cache, &districts, &headers, &params).await?;
+            let short = params.short.unwrap_or(false);
+
+            if typed {
+                $(return Ok(Json(json!($typed_fn(&html, short))));)?
+            }
 
-            (StatusCode::OK, Json(json!(data)))
+            Ok(Json(json!($extract_fn(&html, short))))
         }
     };
 
@@ -83,35 +279,30 @@ macro_rules! endpoint {
         $name:ident,
         single_page: $fetch_fn:path,
         $extract_fn:path,
-        error_msg: $error_msg:expr,
+        page: $page:expr,
         key: name
     ) => {
         pub async fn $name(
             State(cache): State<Cache>,
-            Query(params): Query<LoginParams>
-        ) -> impl IntoResponse {
-            let url = params.link.clone().unwrap_or_else(|| "https://homeaccess.katyisd.org".to_string());
+            State(districts): State<Arc<DistrictRegistry>>,
+            headers: HeaderMap,
+            params: Result<Query<LoginParams>, QueryRejection>
+        ) -> Result<Json<Value>, ApiError> {
+            let Query(params) = params?;
+            raw_only(params.format.as_deref())?;
+            let creds = resolve_credentials(&cache, &districts, &headers, &params).await?;
             let no_cache = params.no_cache.unwrap_or(false);
 
-            let client = match get_or_login(&cache, &params.user, &params.pass, &url, no_cache).await {
-                Ok(c) => c,
-                Err(err) if err == "Invalid username or password" => {
-                    return (StatusCode::UNAUTHORIZED, Json(json!({ "error": err })));
-                }
-                Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e }))),
-            };
+            let (cache, creds) = (&cache, &creds);
 
-            let html = match $fetch_fn(&client, &url, &cache, &params.user, no_cache).await {
-                Ok(body) => body,
-                Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e }))),
-            };
+            let html = with_relogin(cache, creds, no_cache, move |client| async move {
+                $fetch_fn(&client, creds.link(), cache, &creds.username, no_cache).await
+            })
+            .await?;
 
             match $extract_fn(&html) {
-                Some(data) => (StatusCode::OK, Json(json!({ "name": data }))),
-                None => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(json!({ "error": $error_msg })),
-                ),
+                Some(data) => Ok(Json(json!({ "name": data }))),
+                None => Err(ApiError::ParseFailure { page: $page.to_string() }),
             }
         }
     };
@@ -120,34 +311,29 @@ macro_rules! endpoint {
         $name:ident,
         single_page: $fetch_fn:path,
         $extract_fn:path,
-        error_msg: $error_msg:expr
+        page: $page:expr
     ) => {
         pub async fn $name(
             State(cache): State<Cache>,
-            Query(params): Query<LoginParams>
-        ) -> impl IntoResponse {
-            let url = params.link.clone().unwrap_or_else(|| "https://homeaccess.katyisd.org".to_string());
+            State(districts): State<Arc<DistrictRegistry>>,
+            headers: HeaderMap,
+            params: Result<Query<LoginParams>, QueryRejection>
+        ) -> Result<Json<Value>, ApiError> {
+            let Query(params) = params?;
+            raw_only(params.format.as_deref())?;
+            let creds = resolve_credentials(&cache, &districts, &headers, &params).await?;
             let no_cache = params.no_cache.unwrap_or(false);
 
-            let client = match get_or_login(&cache, &params.user, &params.pass, &url, no_cache).await {
-                Ok(c) => c,
-                Err(err) if err == "Invalid username or password" => {
-                    return (StatusCode::UNAUTHORIZED, Json(json!({ "error": err })));
-                }
-                Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e }))),
-            };
+            let (cache, creds) = (&cache, &creds);
 
-            let html = match $fetch_fn(&client, &url, &cache, &params.user, no_cache).await {
-                Ok(body) => body,
-                Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e }))),
-            };
+            let html = with_relogin(cache, creds, no_cache, move |client| async move {
+                $fetch_fn(&client, creds.link(), cache, &creds.username, no_cache).await
+            })
+            .await?;
 
             match $extract_fn(&html) {
-                Some(data) => (StatusCode::OK, Json(json!(data))),
-                None => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(json!({ "error": $error_msg })),
-                ),
+                Some(data) => Ok(Json(json!(data))),
+                None => Err(ApiError::ParseFailure { page: $page.to_string() }),
             }
         }
     };
@@ -156,46 +342,324 @@ macro_rules! endpoint {
         $name:ident,
         vec_result: $fetch_fn:path,
         $extract_fn:path
+        $(, typed: $typed_fn:path)?
+        $(, select: $select:ident)?
     ) => {
         pub async fn $name(
             State(cache): State<Cache>,
-            Query(params): Query<LoginParams>
-        ) -> impl IntoResponse {
-            let url = params.link.clone().unwrap_or_else(|| "https://homeaccess.katyisd.org".to_string());
+            State(districts): State<Arc<DistrictRegistry>>,
+            headers: HeaderMap,
+            params: Result<Query<LoginParams>, QueryRejection>
+        ) -> Result<Json<Value>, ApiError> {
+            let Query(params) = params?;
+            let typed = endpoint!(@typed params $(, $typed_fn)?)?;
+            let creds = resolve_credentials(&cache, &districts, &headers, &params).await?;
             let no_cache = params.no_cache.unwrap_or(false);
 
-            let client = match get_or_login(&cache, &params.user, &params.pass, &url, no_cache).await {
-                Ok(c) => c,
-                Err(err) if err == "Invalid username or password" => {
-                    return (StatusCode::UNAUTHORIZED, Json(json!({ "error": err })));
-                }
-                Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e }))),
-            };
+            let (cache, creds) = (&cache, &creds);
+            $(let $select = params.$select.as_deref();)?
 
-            let html = match $fetch_fn(&client, &url, &cache, &params.user, no_cache).await {
-                Ok(body) => body,
-                Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e }))),
-            };
+            let html = with_relogin(cache, creds, no_cache, move |client| async move {
+                $fetch_fn(&client, creds.link(), cache, &creds.username, no_cache $(, $select)?).await
+            })
+            .await?;
 
-            let data = $extract_fn(&html);
+            if typed {
+                $(return Ok(Json(json!($typed_fn(&html))));)?
+            }
 
-            (StatusCode::OK, Json(json!(data)))
+            Ok(Json(json!($extract_fn(&html))))
         }
     };
 }
 
+pub async fn login(
+    State(cache): State<Cache>,
+    State(districts): State<Arc<DistrictRegistry>>,
+    body: Result<Json<LoginRequest>, JsonRejection>,
+) -> Result<Json<Value>, ApiError> {
+    let Json(body) = body?;
+    let district = resolve_district(&districts, body.district.as_deref(), body.link.as_deref())?;
+
+    let client = login_handler(&body.user, &body.pass, &district).await?;
+
+    cache.set_client(&body.user, &district.base_url, &body.pass, client).await;
+    let token = cache.create_session(&body.user, &body.pass, &district).await;
+
+    Ok(Json(json!({
+        "token": token,
+        "token_type": "Bearer",
+        "expires_in": cache.session_ttl().as_secs(),
+    })))
+}
+
+pub async fn logout(
+    State(cache): State<Cache>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, ApiError> {
+    let token = bearer_token(&headers)
+        .ok_or_else(|| ApiError::BadRequest("Missing Authorization: Bearer token".to_string()))?;
+
+    let session = cache.remove_session(token).await.ok_or(ApiError::SessionExpired)?;
+    cache.remove_client(&session.username, &session.district.base_url).await;
+
+    Ok(Json(json!({ "message": "Logged out" })))
+}
+
+pub async fn what_if(
+    State(cache): State<Cache>,
+    State(districts): State<Arc<DistrictRegistry>>,
+    headers: HeaderMap,
+    params: Result<Query<LoginParams>, QueryRejection>,
+    body: Result<Json<WhatIfRequest>, JsonRejection>,
+) -> Result<Json<Value>, ApiError> {
+    let Query(params) = params?;
+    raw_only(params.format.as_deref())?;
+    let Json(body) = body?;
+    let html = load_assignments_page(&cache, &districts, &headers, &params).await?;
+    let gradebook = extract_gradebook_typed(&html, params.short.unwrap_or(false));
+
+    let (class_name, class) = find_class(&gradebook, &body.class)?;
+    let result = calculator::what_if(class_name, class, &body)?;
+
+    Ok(Json(json!(result)))
+}
+
+pub async fn get_gpa(
+    State(cache): State<Cache>,
+    State(districts): State<Arc<DistrictRegistry>>,
+    State(rules): State<Arc<GpaRules>>,
+    headers: HeaderMap,
+    params: Result<Query<LoginParams>, QueryRejection>,
+) -> Result<Json<Value>, ApiError> {
+    let Query(params) = params?;
+    raw_only(params.format.as_deref())?;
+    let html = load_assignments_page(&cache, &districts, &headers, &params).await?;
+
+    Ok(Json(json!(project_gpa(&rules, &html, params.short.unwrap_or(false)))))
+}
+
+pub async fn get_graduation(
+    State(cache): State<Cache>,
+    State(districts): State<Arc<DistrictRegistry>>,
+    State(plan): State<Arc<GraduationPlan>>,
+    headers: HeaderMap,
+    params: Result<Query<LoginParams>, QueryRejection>,
+) -> Result<Json<Value>, ApiError> {
+    let Query(params) = params?;
+    raw_only(params.format.as_deref())?;
+    let creds = resolve_credentials(&cache, &districts, &headers, &params).await?;
+    let no_cache = params.no_cache.unwrap_or(false);
+
+    let (cache, creds) = (&cache, &creds);
+
+    let (transcript, assignments) = with_relogin(cache, creds, no_cache, move |client| async move {
+        let transcript = fetch_transcript_page(&client, creds.link(), cache, &creds.username, no_cache).await?;
+        let assignments = fetch_assignments_page(&client, creds.link(), cache, &creds.username, no_cache).await?;
+        Ok((transcript, assignments))
+    })
+    .await?;
+
+    Ok(Json(json!(graduation::audit(&plan, &transcript, &assignments))))
+}
+
+pub async fn get_attendance(
+    State(cache): State<Cache>,
+    State(districts): State<Arc<DistrictRegistry>>,
+    headers: HeaderMap,
+    params: Result<Query<LoginParams>, QueryRejection>,
+) -> Result<Json<Value>, ApiError> {
+    let Query(params) = params?;
+    raw_only(params.format.as_deref())?;
+    let today = chrono::Local::now().date_naive();
+    let (from, to) = attendance::date_range(params.from.as_deref(), params.to.as_deref(), today)?;
+    let creds = resolve_credentials(&cache, &districts, &headers, &params).await?;
+    let no_cache = params.no_cache.unwrap_or(false);
+
+    let (cache, creds) = (&cache, &creds);
+    let months = &attendance::months_between(from, to);
+
+    let pages = with_relogin(cache, creds, no_cache, move |client| async move {
+        fetch_attendance_months(&client, creds.link(), cache, &creds.username, no_cache, months).await
+    })
+    .await?;
+
+    Ok(Json(json!(attendance::summarize(&pages, from, to)?)))
+}
+
+pub async fn get_calendar(
+    State(cache): State<Cache>,
+    State(districts): State<Arc<DistrictRegistry>>,
+    headers: HeaderMap,
+    params: Result<Query<LoginParams>, QueryRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Query(params) = params?;
+    raw_only(params.format.as_deref())?;
+    let creds = resolve_credentials(&cache, &districts, &headers, &params).await?;
+    let html = fetch_assignments_for(&cache, &creds, &params).await?;
+
+    let owner = format!("{}@{}", creds.username.to_lowercase(), creds.link());
+    let ics = calendar::assignments_calendar(&html, params.short.unwrap_or(false), &owner, chrono::Utc::now());
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, "inline; filename=\"assignments.ics\""),
+        ],
+        ics,
+    ))
+}
+
+pub async fn get_changes(
+    State(cache): State<Cache>,
+    State(districts): State<Arc<DistrictRegistry>>,
+    headers: HeaderMap,
+    params: Result<Query<LoginParams>, QueryRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Query(params) = params?;
+    raw_only(params.format.as_deref())?;
+    let creds = resolve_credentials(&cache, &districts, &headers, &params).await?;
+    let no_cache = params.no_cache.unwrap_or(false);
+
+    let (cache, creds) = (&cache, &creds);
+
+    let html = with_relogin(cache, creds, no_cache, move |client| async move {
+        fetch_assignments_page(&client, creds.link(), cache, &creds.username, no_cache).await
+    })
+    .await?;
+
+    let gradebook = extract_gradebook_typed(&html, false);
+    let etag = changes::gradebook_etag(&gradebook);
+    let checked_at = chrono::Utc::now();
+
+    let caller = bearer_token(&headers).unwrap_or_default();
+    let history = cache.get_snapshots(&creds.username, creds.link(), caller).await;
+    let baseline = changes::select_baseline(&history, params.since.as_deref())?;
+
+    let report = ChangeReport {
+        etag: etag.clone(),
+        previous_etag: baseline.map(|b| b.etag.clone()),
+        since: baseline.map(|b| b.taken_at),
+        checked_at,
+        changes: baseline
+            .map(|b| changes::diff(&b.gradebook, &gradebook, params.short.unwrap_or(false)))
+            .unwrap_or_default(),
+    };
+
+    cache
+        .push_snapshot(&creds.username, creds.link(), caller, Snapshot { etag: etag.clone(), taken_at: checked_at, gradebook })
+        .await;
+
+    Ok(([(header::ETAG, format!("\"{}\"", etag))], Json(json!(report))))
+}
+
+pub async fn create_subscription(
+    State(cache): State<Cache>,
+    State(districts): State<Arc<DistrictRegistry>>,
+    State(webhooks): State<Webhooks>,
+    headers: HeaderMap,
+    params: Result<Query<LoginParams>, QueryRejection>,
+    body: Result<Json<SubscriptionRequest>, JsonRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Query(params) = params?;
+    raw_only(params.format.as_deref())?;
+    let Json(body) = body?;
+    let creds = resolve_credentials(&cache, &districts, &headers, &params).await?;
+    get_or_login(&cache, &creds, false).await?;
+
+    let (subscription, secret) = webhooks
+        .subscribe(&creds.username, &creds.password, &creds.district, &body.url, body.secret, body.short.unwrap_or(false))
+        .await?;
+
+    let mut response = json!(subscription);
+    response["secret"] = json!(secret);
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+pub async fn list_subscriptions(
+    State(cache): State<Cache>,
+    State(districts): State<Arc<DistrictRegistry>>,
+    State(webhooks): State<Webhooks>,
+    headers: HeaderMap,
+    params: Result<Query<LoginParams>, QueryRejection>,
+) -> Result<Json<Value>, ApiError> {
+    let Query(params) = params?;
+    raw_only(params.format.as_deref())?;
+    let creds = resolve_credentials(&cache, &districts, &headers, &params).await?;
+    get_or_login(&cache, &creds, false).await?;
+
+    Ok(Json(json!(webhooks.list(&creds.username, creds.link()).await)))
+}
+
+pub async fn delete_subscription(
+    State(cache): State<Cache>,
+    State(districts): State<Arc<DistrictRegistry>>,
+    State(webhooks): State<Webhooks>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    params: Result<Query<LoginParams>, QueryRejection>,
+) -> Result<Json<Value>, ApiError> {
+    let Query(params) = params?;
+    raw_only(params.format.as_deref())?;
+    let creds = resolve_credentials(&cache, &districts, &headers, &params).await?;
+    get_or_login(&cache, &creds, false).await?;
+
+    webhooks.unsubscribe(&creds.username, creds.link(), &id).await?;
+    Ok(Json(json!({ "message": "Subscription deleted" })))
+}
+
+pub async fn get_districts(State(districts): State<Arc<DistrictRegistry>>) -> Json<Value> {
+    let list: Vec<Value> = districts
+        .all()
+        .iter()
+        .map(|d| json!({ "id": d.id, "name": d.name, "base_url": d.base_url }))
+        .collect();
+
+    Json(json!({
+        "default": districts.default_district().id,
+        "districts": list,
+    }))
+}
+
+pub async fn get_login_options(
+    State(districts): State<Arc<DistrictRegistry>>,
+    params: Result<Query<LoginParams>, QueryRejection>,
+) -> Result<Json<Value>, ApiError> {
+    let Query(params) = params?;
+    raw_only(params.format.as_deref())?;
+    let district = resolve_district(&districts, params.district.as_deref(), params.link.as_deref())?;
+    let form = login_options(&district).await?;
+
+    Ok(Json(json!({
+        "link": district.base_url,
+        "district": district.id,
+        "databases": form.databases,
+        "verification_options": form.verification_options,
+    })))
+}
+
 pub async fn root() -> impl IntoResponse {
     let message = json!({
         "title": "Welcome to the Home Access Center API!",
         "message": "Visit the docs at https://hac.packjack.dev/docs",
         "routes": [
-            "/api/name", "/api/assignments", "/api/info", "/api/averages", "/api/weightings", "/api/classes", "/api/reportcard", "/api/ipr", "/api/transcript", "/api/rank"
+            "/api/login", "/api/logout", "/api/districts", "/api/login-options", "/api/name", "/api/assignments", "/api/info", "/api/averages", "/api/weightings", "/api/classes", "/api/whatif", "/api/gpa", "/api/schedule", "/api/attendance", "/api/calendar.ics", "/api/changes", "/api/subscriptions", "/api/reportcard", "/api/reportcard/runs", "/api/ipr", "/api/ipr/dates", "/api/transcript", "/api/graduation", "/api/rank"
         ],
+        "auth": "POST /api/login for a session token, then send it as Authorization: Bearer <token>",
         "cache_param": "Add ?no_cache=true to any endpoint to bypass cache"
     });
     Json(message)
 }
 
+pub async fn metrics() -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        upstream::metrics(),
+    )
+}
+
 pub async fn serve_openapi_yaml() -> impl IntoResponse {
     let openapi_content = include_str!("../openapi.yaml");
     (
@@ -258,29 +722,33 @@ endpoint!(
 
 endpoint!(
     get_averages,
-    assignments_page_scraper: extract_averages
+    assignments_page_scraper: extract_averages,
+    typed: extract_averages_typed
 );
 
 endpoint!(
     get_assignments,
-    assignments_page_scraper: extract_assignments
+    assignments_page_scraper: extract_assignments,
+    typed: extract_assignments_typed
 );
 
 endpoint!(
     get_weightings,
-    assignments_page_scraper: extract_weightings
+    assignments_page_scraper: extract_weightings,
+    typed: extract_weightings_typed
 );
 
 endpoint!(
     get_gradebook,
-    assignments_page_scraper: extract_gradebook
+    assignments_page_scraper: extract_gradebook,
+    typed: extract_gradebook_typed
 );
 
 endpoint!(
     get_name,
     single_page: fetch_name_page,
     extract_name,
-    error_msg: "Failed to parse name",
+    page: "Classwork",
     key: name
 );
 
@@ -288,25 +756,48 @@ endpoint!(
     get_info,
     single_page: fetch_info_page,
     extract_info,
-    error_msg: "Failed to parse student info"
+    page: "Registration.aspx"
+);
+
+endpoint!(
+    get_schedule,
+    vec_result: fetch_schedule_page,
+    extract_schedule
 );
 
 endpoint!(
     get_report_card,
+    vec_result: fetch_report_page_for_run,
+    extract_report_cards,
+    typed: extract_report_cards_typed,
+    select: run
+);
+
+endpoint!(
+    get_report_card_runs,
     vec_result: fetch_report_page,
-    extract_report_cards
+    extract_report_card_runs
 );
 
 endpoint!(
     get_progress_report,
+    vec_result: fetch_progress_page_for_date,
+    extract_progress,
+    typed: extract_progress_typed,
+    select: date
+);
+
+endpoint!(
+    get_progress_dates,
     vec_result: fetch_progress_page,
-    extract_progress
+    extract_progress_dates
 );
 
 endpoint!(
     get_transcript,
     vec_result: fetch_transcript_page,
-    extract_transcript
+    extract_transcript,
+    typed: extract_transcript_typed
 );
 
 endpoint!(
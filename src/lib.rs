@@ -0,0 +1,21 @@
+pub mod routes;
+pub mod handlers;
+pub mod auth;
+pub mod scraping;
+pub mod fetchers;
+pub mod cache;
+pub mod error;
+pub mod models;
+pub mod grades;
+pub mod calculator;
+pub mod gpa;
+pub mod graduation;
+pub mod attendance;
+pub mod calendar;
+pub mod changes;
+pub mod districts;
+pub mod state;
+pub mod config;
+pub mod upstream;
+pub mod tls;
+pub mod webhooks;
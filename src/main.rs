@@ -1,41 +1,114 @@
-mod routes;
-mod handlers;
-mod auth;
-mod scraping;
-mod fetchers;
-mod cache;
-
 use std::net::SocketAddr;
-use cache::Cache;
+use std::sync::Arc;
+use hac_api::cache::Cache;
+use hac_api::config::Config;
+use hac_api::districts::DistrictRegistry;
+use hac_api::gpa::GpaRules;
+use hac_api::graduation::GraduationPlan;
+use hac_api::routes;
+use hac_api::state::AppState;
+use hac_api::tls;
+use hac_api::upstream;
+use hac_api::webhooks::{self, Webhooks};
 use std::time::Duration;
 
+fn exit_with(message: String) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+fn load_plans(config: &Config) -> Result<(GpaRules, GraduationPlan), String> {
+    let rules = match &config.gpa_rules_file {
+        Some(path) => GpaRules::load(path)?,
+        None => GpaRules::default(),
+    };
+    let plan = match &config.graduation_plan_file {
+        Some(path) => GraduationPlan::load(path)?,
+        None => GraduationPlan::default(),
+    };
+    Ok((rules, plan))
+}
+
+fn load_districts(config: &Config) -> Result<DistrictRegistry, String> {
+    let mut districts = match &config.districts_file {
+        Some(path) => DistrictRegistry::load(path)?,
+        None => DistrictRegistry::builtin(),
+    };
+
+    if let Some(link) = &config.default_link {
+        districts.set_default_link(link);
+    }
+    districts.restrict(&config.allowed_districts)?;
+    Ok(districts)
+}
+
 #[tokio::main]
 async fn main() {
-    let login_cache = 30 * 60;
-    let page_cache = 5 * 60;
-    let cache = Cache::new(login_cache, page_cache);
-    
+    let config = Config::load().unwrap_or_else(|e| exit_with(e));
+    let districts = load_districts(&config).unwrap_or_else(|e| exit_with(format!("Invalid configuration:\n  - {}", e)));
+    upstream::register_districts(districts.all());
+    let (gpa_rules, graduation_plan) = load_plans(&config).unwrap_or_else(|e| exit_with(format!("Invalid configuration:\n  - {}", e)));
+    let tls = match (&config.tls_cert, &config.tls_key) {
+        (Some(cert), Some(key)) => Some(tls::acceptor(cert, key).unwrap_or_else(|e| exit_with(e))),
+        _ => None,
+    };
+
+    tracing_subscriber::fmt()
+        .with_max_level(config.log_level.parse::<tracing::Level>().unwrap_or(tracing::Level::INFO))
+        .init();
+
+    upstream::configure(config.upstream_settings());
+
+    let cache = Cache::new(config.login_ttl_secs, config.page_ttl_secs, config.closed_page_ttl_secs);
+
     let cache_cleaner = cache.clone();
+    let cleaner_interval = Duration::from_secs(config.cleaner_interval_secs);
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(10 * 60));
+        let mut interval = tokio::time::interval(cleaner_interval);
         loop {
             interval.tick().await;
             cache_cleaner.clear_expired().await;
         }
     });
 
-    let app = routes::create_router(cache);
+    let webhooks = Webhooks::new(config.webhook_settings());
+
+    let (poll_cache, poll_webhooks) = (cache.clone(), webhooks.clone());
+    let webhook_interval = Duration::from_secs(config.webhook_interval_secs);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(webhook_interval);
+        loop {
+            interval.tick().await;
+            webhooks::poll_once(&poll_cache, &poll_webhooks).await;
+        }
+    });
+
+    let district_count = districts.all().len();
+    let default_district = districts.default_district().id.clone();
+
+    let app = routes::create_router(AppState {
+        cache,
+        districts: Arc::new(districts),
+        webhooks,
+        gpa_rules: Arc::new(gpa_rules),
+        graduation_plan: Arc::new(graduation_plan),
+    });
+
+    let addr: SocketAddr = config.bind.parse().expect("bind address is validated on load");
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .unwrap_or_else(|e| exit_with(format!("Failed to bind {}: {}", addr, e)));
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
-    println!("Listening on http://{}", addr);
-    println!("Cache configuration:");
-    println!("  - Login sessions: {} minutes", login_cache);
-    println!("  - Page data: {} minutes", page_cache);
+    tracing::info!("Listening on {}://{}", if tls.is_some() { "https" } else { "http" }, addr);
+    tracing::info!("Cache configuration:");
+    tracing::info!("  - Login sessions: {} minutes", config.login_ttl_secs / 60);
+    tracing::info!("  - Page data: {} minutes", config.page_ttl_secs / 60);
+    tracing::info!("  - Closed grading periods: {} hours", config.closed_page_ttl_secs / 3600);
+    tracing::info!("  - Webhook polling: every {} minutes", config.webhook_interval_secs / 60);
+    tracing::info!("Districts loaded: {} (default {})", district_count, default_district);
 
-    axum::serve(
-        tokio::net::TcpListener::bind(addr).await.unwrap(),
-        app.into_make_service(),
-    )
-    .await
-    .unwrap();
+    match tls {
+        Some(acceptor) => tls::serve(listener, app, acceptor).await,
+        None => axum::serve(listener, app.into_make_service()).await.unwrap(),
+    }
 }
@@ -0,0 +1,146 @@
+use serde::Serialize;
+use indexmap::IndexMap;
+use crate::grades::Grade;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Assignment {
+    pub date_due: String,
+    pub date_assigned: String,
+    pub name: String,
+    pub category: String,
+    pub score: Grade,
+    pub total_points: Option<f64>,
+    pub weight: Option<f64>,
+    pub weighted_score: Option<f64>,
+    pub weighted_total_points: Option<f64>,
+    pub percentage: Grade,
+    pub exempt: bool,
+    pub missing: bool,
+    pub late: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Category {
+    pub name: String,
+    pub points: Option<f64>,
+    pub max_points: Option<f64>,
+    pub percent: Grade,
+    pub weight: Option<f64>,
+    pub category_points: Option<f64>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ClassGrades {
+    pub average: Grade,
+    pub assignments: Vec<Assignment>,
+    pub categories: Vec<Category>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ProgressEntry {
+    pub course: String,
+    pub description: String,
+    pub period: String,
+    pub teacher: String,
+    pub room: String,
+    pub mark: Grade,
+    pub comments: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ReportCardEntry {
+    pub course: String,
+    pub description: String,
+    pub period: String,
+    pub teacher: String,
+    pub room: String,
+    pub attempted_credit: Option<f64>,
+    pub earned_credit: Option<f64>,
+    pub grades: IndexMap<String, Grade>,
+    pub exams: IndexMap<String, Grade>,
+    pub semesters: IndexMap<String, Grade>,
+    pub conduct: IndexMap<String, String>,
+    pub comments: Vec<String>,
+    pub other: IndexMap<String, String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DropdownOption {
+    pub value: String,
+    pub label: String,
+    pub selected: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TranscriptCourse {
+    pub code: String,
+    pub description: String,
+    pub grade: Grade,
+    pub credit: Option<f64>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Semester {
+    pub year: String,
+    pub semester: String,
+    pub grade_level: String,
+    pub school: String,
+    pub courses: Vec<TranscriptCourse>,
+    pub credits: Option<f64>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct GpaEntry {
+    pub description: String,
+    pub gpa: Option<f64>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Transcript {
+    pub semesters: Vec<Semester>,
+    pub cumulative_gpa: Vec<GpaEntry>,
+    pub rank: Option<String>,
+    pub quartile: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ScheduleEntry {
+    pub course: String,
+    pub description: String,
+    pub period: String,
+    pub teacher: String,
+    pub teacher_email: Option<String>,
+    pub room: String,
+    pub days: String,
+    pub marking_periods: Vec<String>,
+    pub building: String,
+    pub status: String,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct AttendanceCode {
+    pub code: String,
+    pub description: String,
+    pub color: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct AttendanceRecord {
+    pub period: Option<String>,
+    pub code: String,
+    pub description: String,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct AttendanceDay {
+    pub date: String,
+    pub records: Vec<AttendanceRecord>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct AttendanceMonth {
+    pub year: i32,
+    pub month: u32,
+    pub legend: Vec<AttendanceCode>,
+    pub days: Vec<AttendanceDay>,
+}
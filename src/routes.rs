@@ -1,13 +1,18 @@
-use axum::{routing::get, Router};
-use crate::cache::Cache;
-use crate::handlers::{root, get_averages, get_classes, get_info, get_name, get_assignments, get_gradebook, get_weightings, get_report_card, get_progress_report, get_transcript, get_rank, serve_openapi_yaml, serve_docs};
+use axum::{middleware, routing::{delete, get, post}, Router};
+use crate::state::AppState;
+use crate::handlers::{root, login, logout, get_districts, get_login_options, get_averages, get_classes, get_info, get_name, get_assignments, get_gradebook, get_weightings, what_if, get_gpa, get_schedule, get_attendance, get_calendar, get_changes, create_subscription, list_subscriptions, delete_subscription, get_report_card, get_report_card_runs, get_progress_report, get_progress_dates, get_transcript, get_graduation, get_rank, metrics, serve_openapi_yaml, serve_docs, deprecate_query_credentials};
 
-pub fn create_router(cache: Cache) -> Router {
+pub fn create_router(state: AppState) -> Router {
     Router::new()
         .route("/", get(root))
         .route("/api/", get(root))
         .route("/docs", get(serve_docs))
         .route("/openapi.yaml", get(serve_openapi_yaml))
+        .route("/metrics", get(metrics))
+        .route("/api/login", post(login))
+        .route("/api/logout", post(logout))
+        .route("/api/districts", get(get_districts))
+        .route("/api/login-options", get(get_login_options))
         .route("/api/name", get(get_name))
         .route("/api/info", get(get_info))
         .route("/api/classes", get(get_classes))
@@ -15,10 +20,22 @@ pub fn create_router(cache: Cache) -> Router {
         .route("/api/assignments", get(get_assignments))
         .route("/api/gradebook", get(get_gradebook))
         .route("/api/weightings", get(get_weightings))
+        .route("/api/whatif", post(what_if))
+        .route("/api/gpa", get(get_gpa))
+        .route("/api/schedule", get(get_schedule))
+        .route("/api/attendance", get(get_attendance))
+        .route("/api/calendar.ics", get(get_calendar))
+        .route("/api/changes", get(get_changes))
+        .route("/api/subscriptions", get(list_subscriptions).post(create_subscription))
+        .route("/api/subscriptions/{id}", delete(delete_subscription))
         .route("/api/reportcard", get(get_report_card))
+        .route("/api/reportcard/runs", get(get_report_card_runs))
         .route("/api/ipr", get(get_progress_report))
+        .route("/api/ipr/dates", get(get_progress_dates))
         .route("/api/transcript", get(get_transcript))
+        .route("/api/graduation", get(get_graduation))
         .route("/api/rank", get(get_rank))
-        .with_state(cache)
+        .layer(middleware::from_fn(deprecate_query_credentials))
+        .with_state(state)
 }
 
@@ -1,6 +1,13 @@
-use scraper::{Html, Selector};
+use chrono::{Datelike, NaiveDate};
+use scraper::{ElementRef, Html, Selector};
 use std::collections::HashMap;
 use serde_json::{Value, Map, json};
+use crate::grades::{parse_number, Grade, Score};
+use indexmap::IndexMap;
+use crate::models::{
+    Assignment, AttendanceCode, AttendanceDay, AttendanceMonth, AttendanceRecord, Category, ClassGrades, DropdownOption, GpaEntry, ProgressEntry, ReportCardEntry, ScheduleEntry,
+    Semester, Transcript, TranscriptCourse,
+};
 
 pub fn shorten_class_name(full: &str) -> String {
     let mut words: Vec<&str> = full.split_whitespace().collect();
@@ -122,13 +129,7 @@ pub fn extract_averages(html: &str, short: bool) -> HashMap<String, String> {
             .map(|a| a.text().collect::<Vec<_>>().join(" "))
             .unwrap_or_default();
 
-        let average_clean = if average_text.len() > 18 {
-            average_text[18..].trim().to_string()
-        } else {
-            average_text.trim().to_string()
-        };
-
-        results.insert(class_name, average_clean);
+        results.insert(class_name, strip_average_label(&average_text).to_string());
     }
 
     results
@@ -164,21 +165,20 @@ pub fn extract_weightings(html: &str, short: bool) -> HashMap<String, Vec<Vec<St
         let mut class_weightings: Vec<Vec<String>> = Vec::new();
 
         for table in class_group.select(&table_selector) {
-            if let Some(id) = table.value().attr("id") {
-                if id.contains("CourseCategories") {
-                    let rows: Vec<_> = table.select(&row_selector).collect();
-                    if rows.len() <= 1 { continue; }
+            if let Some(id) = table.value().attr("id")
+                && id.contains("CourseCategories") {
+                let rows: Vec<_> = table.select(&row_selector).collect();
+                if rows.len() <= 1 { continue; }
 
-                    let rows_to_process = &rows[1..rows.len().saturating_sub(1)];
+                let rows_to_process = &rows[1..rows.len().saturating_sub(1)];
 
-                    for row in rows_to_process {
-                        let row_data: Vec<String> = row.select(&cell_selector)
-                            .map(|c| c.text().collect::<String>().trim().to_string())
-                            .collect();
+                for row in rows_to_process {
+                    let row_data: Vec<String> = row.select(&cell_selector)
+                        .map(|c| c.text().collect::<String>().trim().to_string())
+                        .collect();
 
-                        if row_data.is_empty() { continue; }
-                        class_weightings.push(row_data);
-                    }
+                    if row_data.is_empty() { continue; }
+                    class_weightings.push(row_data);
                 }
             }
         }
@@ -218,33 +218,31 @@ pub fn extract_assignments(html: &str, short: bool) -> HashMap<String, Vec<Vec<S
         let mut assignments_for_class: Vec<Vec<String>> = Vec::new();
 
         for table in class_group.select(&table_selector) {
-            if let Some(id) = table.value().attr("id") {
-                if id.contains("CourseAssignments") {
-                    let rows: Vec<_> = table.select(&row_selector).collect();
-                    if rows.len() <= 2 { continue; }
-                    
-
-                    let rows_to_process = &rows[1..rows.len().saturating_sub(2)];
-
-                    for row in rows_to_process {
-                        let row_data: Vec<String> = row.select(&cell_selector)
-                            .map(|cell| cell.text().collect::<String>()
-                                .replace("*", "")
-                                .split_whitespace()
-                                .collect::<Vec<_>>()
-                                .join(" "))
-                            .collect();
-
-                        if row_data.is_empty() { continue; }
-
-                        if let Some(first) = row_data.first() {
-                            if ["Major", "Minor", "Other", "Total"].contains(&first.as_str()) {
-                                continue;
-                            }
-                        }
-
-                        assignments_for_class.push(row_data);
+            if let Some(id) = table.value().attr("id")
+                && id.contains("CourseAssignments") {
+                let rows: Vec<_> = table.select(&row_selector).collect();
+                if rows.len() <= 2 { continue; }
+                
+
+                let rows_to_process = &rows[1..rows.len().saturating_sub(2)];
+
+                for row in rows_to_process {
+                    let row_data: Vec<String> = row.select(&cell_selector)
+                        .map(|cell| cell.text().collect::<String>()
+                            .replace("*", "")
+                            .split_whitespace()
+                            .collect::<Vec<_>>()
+                            .join(" "))
+                        .collect();
+
+                    if row_data.is_empty() { continue; }
+
+                    if let Some(first) = row_data.first()
+                        && ["Major", "Minor", "Other", "Total"].contains(&first.as_str()) {
+                        continue;
                     }
+
+                    assignments_for_class.push(row_data);
                 }
             }
         }
@@ -257,6 +255,257 @@ pub fn extract_assignments(html: &str, short: bool) -> HashMap<String, Vec<Vec<S
     ret
 }
 
+fn normalize_header(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn clean_cell_text(cell: ElementRef) -> String {
+    cell.text()
+        .collect::<String>()
+        .replace("*", "")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+struct HeaderTable<'a> {
+    headers: Vec<String>,
+    labels: Vec<String>,
+    rows: Vec<Vec<ElementRef<'a>>>,
+}
+
+impl<'a> HeaderTable<'a> {
+    fn parse(table: ElementRef<'a>) -> Self {
+        let row_selector = Selector::parse("tr").unwrap();
+        let cell_selector = Selector::parse("th, td").unwrap();
+
+        let mut labels: Vec<String> = Vec::new();
+        let mut rows = Vec::new();
+
+        for row in table.select(&row_selector) {
+            let class_attr = row.value().attr("class").unwrap_or_default();
+            if class_attr.contains("sg-asp-table-header-row") && labels.is_empty() {
+                labels = row.select(&cell_selector).map(clean_cell_text).collect();
+            } else if class_attr.contains("sg-asp-table-data-row") {
+                rows.push(row.select(&cell_selector).collect());
+            }
+        }
+
+        let headers = labels.iter().map(|l| normalize_header(l)).collect();
+
+        Self { headers, labels, rows }
+    }
+
+    fn column(&self, header: &str) -> Option<usize> {
+        self.headers.iter().position(|h| h == header)
+    }
+
+    fn cell(&self, row: &[ElementRef<'a>], header: &str) -> Option<ElementRef<'a>> {
+        self.column(header).and_then(|i| row.get(i).copied())
+    }
+
+    fn text(&self, row: &[ElementRef<'a>], header: &str) -> String {
+        self.cell(row, header).map(clean_cell_text).unwrap_or_default()
+    }
+}
+
+fn title_contains(cell: Option<ElementRef>, word: &str) -> bool {
+    cell.and_then(|c| c.value().attr("title"))
+        .is_some_and(|t| t.to_lowercase().contains(word))
+}
+
+fn parse_assignment_table(table: ElementRef) -> Vec<Assignment> {
+    let table = HeaderTable::parse(table);
+    let mut assignments = Vec::new();
+
+    for row in &table.rows {
+        let name = table.text(row, "assignment");
+        if name.is_empty() {
+            continue;
+        }
+
+        let score = Grade::parse(&table.text(row, "score"));
+        let score_cell = table.cell(row, "score");
+        let exempt = score.score == Score::Exempt || title_contains(score_cell, "exempt");
+        let missing = score.score == Score::Missing || title_contains(score_cell, "missing");
+        let late = matches!(score.raw.to_ascii_uppercase().as_str(), "L" | "LATE")
+            || title_contains(score_cell, "late");
+
+        assignments.push(Assignment {
+            date_due: table.text(row, "date due"),
+            date_assigned: table.text(row, "date assigned"),
+            name,
+            category: table.text(row, "category"),
+            total_points: parse_number(&table.text(row, "total points")),
+            weight: parse_number(&table.text(row, "weight")),
+            weighted_score: parse_number(&table.text(row, "weighted score")),
+            weighted_total_points: parse_number(&table.text(row, "weighted total points")),
+            percentage: Grade::parse(&table.text(row, "percentage")),
+            score,
+            exempt,
+            missing,
+            late,
+        });
+    }
+
+    assignments
+}
+
+fn parse_category_table(table: ElementRef) -> Vec<Category> {
+    let table = HeaderTable::parse(table);
+
+    table
+        .rows
+        .iter()
+        .map(|row| Category {
+            name: table.text(row, "category"),
+            points: parse_number(&table.text(row, "student's points")),
+            max_points: parse_number(&table.text(row, "maximum points")),
+            percent: Grade::parse(&table.text(row, "percent")),
+            weight: parse_number(&table.text(row, "category weight")),
+            category_points: parse_number(&table.text(row, "category points")),
+        })
+        .filter(|c| !c.name.is_empty() && c.name != "Total")
+        .collect()
+}
+
+fn class_tables<'a>(
+    class_group: ElementRef<'a>,
+    id_fragment: &'a str,
+) -> impl Iterator<Item = ElementRef<'a>> {
+    let table_selector = Selector::parse("table.sg-asp-table").unwrap();
+    class_group
+        .select(&table_selector)
+        .filter(move |t| t.value().attr("id").is_some_and(|id| id.contains(id_fragment)))
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+fn class_heading(class_group: ElementRef, short: bool) -> Option<String> {
+    let header_selector = Selector::parse("div.sg-header").unwrap();
+    let link_selector = Selector::parse("a.sg-header-heading").unwrap();
+
+    let header = class_group.select(&header_selector).next()?;
+    let header_text = header
+        .select(&link_selector)
+        .next()
+        .map(|el| el.text().collect::<String>())
+        .unwrap_or_default();
+
+    Some(normalize_class_name(&header_text, short))
+}
+
+fn class_average(class_group: ElementRef) -> Grade {
+    let average_selector = Selector::parse("span.sg-header-heading").unwrap();
+
+    let average_text = class_group
+        .select(&average_selector)
+        .next()
+        .map(|a| a.text().collect::<Vec<_>>().join(" "))
+        .unwrap_or_default();
+
+    Grade::parse(strip_average_label(&average_text))
+}
+
+fn strip_average_label(text: &str) -> &str {
+    let text = text.trim();
+    text.strip_prefix("Classwork Average").unwrap_or(text).trim()
+}
+
+pub fn extract_assignments_typed(html: &str, short: bool) -> HashMap<String, Vec<Assignment>> {
+    let document = Html::parse_document(html);
+    let class_selector = Selector::parse("div.AssignmentClass").unwrap();
+
+    let mut ret: HashMap<String, Vec<Assignment>> = HashMap::new();
+
+    for class_group in document.select(&class_selector) {
+        let class_name = match class_heading(class_group, short) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let assignments_for_class: Vec<Assignment> = class_tables(class_group, "CourseAssignments")
+            .flat_map(parse_assignment_table)
+            .collect();
+
+        if !assignments_for_class.is_empty() {
+            ret.insert(class_name, assignments_for_class);
+        }
+    }
+
+    ret
+}
+
+pub fn extract_averages_typed(html: &str, short: bool) -> HashMap<String, Grade> {
+    let document = Html::parse_document(html);
+    let class_selector = Selector::parse("div.AssignmentClass").unwrap();
+    let header_selector = Selector::parse("div.sg-header").unwrap();
+
+    let mut results = HashMap::new();
+
+    for class_group in document.select(&class_selector) {
+        let header_text = class_group
+            .select(&header_selector)
+            .next()
+            .map(|h| h.text().collect::<Vec<_>>().join(" "))
+            .unwrap_or_default();
+
+        results.insert(normalize_class_name(&header_text, short), class_average(class_group));
+    }
+
+    results
+}
+
+pub fn extract_weightings_typed(html: &str, short: bool) -> HashMap<String, Vec<Category>> {
+    let document = Html::parse_document(html);
+    let class_selector = Selector::parse("div.AssignmentClass").unwrap();
+
+    let mut weightings = HashMap::new();
+
+    for class_group in document.select(&class_selector) {
+        let class_name = match class_heading(class_group, short) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let categories: Vec<Category> = class_tables(class_group, "CourseCategories")
+            .flat_map(parse_category_table)
+            .collect();
+
+        if !categories.is_empty() {
+            weightings.insert(class_name, categories);
+        }
+    }
+
+    weightings
+}
+
+pub fn extract_gradebook_typed(html: &str, short: bool) -> HashMap<String, ClassGrades> {
+    let document = Html::parse_document(html);
+    let class_selector = Selector::parse("div.AssignmentClass").unwrap();
+
+    let mut gradebook = HashMap::new();
+
+    for class_group in document.select(&class_selector) {
+        let class_name = match class_heading(class_group, short) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        gradebook.insert(class_name, ClassGrades {
+            average: class_average(class_group),
+            assignments: class_tables(class_group, "CourseAssignments")
+                .flat_map(parse_assignment_table)
+                .collect(),
+            categories: class_tables(class_group, "CourseCategories")
+                .flat_map(parse_category_table)
+                .collect(),
+        });
+    }
+
+    gradebook
+}
+
 pub fn extract_gradebook(html: &str, short: bool) -> Map<String, Value> {
     let averages = extract_averages(html, short);
     let assignments = extract_assignments(html, short);
@@ -309,39 +558,46 @@ pub fn extract_gradebook(html: &str, short: bool) -> Map<String, Value> {
     combined
 }
 
+// The untyped report card keeps its positional rows: course details followed by every grade,
+// exam, semester and conduct column in page order. Columns are picked by header, so districts
+// with a different layout still line up.
 pub fn extract_report_cards(html: &str) -> Vec<Vec<String>> {
-    use scraper::{Html, Selector};
-
     let document = Html::parse_document(html);
-    let td_selector = Selector::parse("td").unwrap();
+    let table_selector = Selector::parse("table.sg-asp-table").unwrap();
 
-    let mut all_cells: Vec<String> = Vec::new();
+    let mut rows = Vec::new();
 
-    for (i, td) in document.select(&td_selector).enumerate() {
-        if i >= 32 { 
-            let text = td.text().collect::<Vec<_>>().join(" ").trim().to_string();
-            all_cells.push(text);
+    for table in document.select(&table_selector) {
+        let table = HeaderTable::parse(table);
+        if table.column("course").is_none() || table.column("description").is_none() {
+            continue;
         }
-    }
 
-    let mut rows: Vec<Vec<String>> = Vec::new();
-    let mut current_row: Vec<String> = Vec::new();
-
-    for cell in all_cells {
-        current_row.push(cell);
-        if current_row.len() == 32 {
-            rows.push(current_row);
-            current_row = Vec::new();
-        }
-    }
+        let keep: Vec<bool> = table
+            .headers
+            .iter()
+            .map(|h| {
+                !matches!(
+                    report_card_column(h),
+                    ReportCardColumn::AttemptedCredit
+                        | ReportCardColumn::EarnedCredit
+                        | ReportCardColumn::Comment
+                        | ReportCardColumn::Other
+                )
+            })
+            .collect();
 
-    for row in &mut rows {
-        if row.len() >= 32 {
-            row.drain(23..32);
-        }
+        for row in &table.rows {
+            let cells: Vec<String> = keep
+                .iter()
+                .zip(row)
+                .filter(|(keep, _)| **keep)
+                .map(|(_, cell)| clean_cell_text(*cell))
+                .collect();
 
-        if row.len() >= 7 {
-            row.drain(5..7);
+            if !table.text(row, "course").is_empty() {
+                rows.push(cells);
+            }
         }
     }
 
@@ -407,29 +663,26 @@ pub fn extract_transcript(html: &str) -> HashMap<String, Value> {
             if i==0 {
                 continue;
             }
-            if let Some(class_attr) = row.value().attr("class") {
-                if class_attr.contains("sg-asp-table-header-row") 
-                    || class_attr.contains("sg-asp-table-data-row") 
-                {
-                    let mut data_row: Vec<String> = Vec::new();
-                    for cell in row.select(&cell_selector) {
-                        let text = cell.text().collect::<Vec<_>>().join("").trim().to_string();
-                        data_row.push(text);
-                    }
-                    if !data_row.is_empty() {
-                        final_data.push(data_row);
-                    }
+            if let Some(class_attr) = row.value().attr("class")
+                && (class_attr.contains("sg-asp-table-header-row")
+                    || class_attr.contains("sg-asp-table-data-row")) {
+                let mut data_row: Vec<String> = Vec::new();
+                for cell in row.select(&cell_selector) {
+                    let text = cell.text().collect::<Vec<_>>().join("").trim().to_string();
+                    data_row.push(text);
+                }
+                if !data_row.is_empty() {
+                    final_data.push(data_row);
                 }
             }
         }
         semester.insert("data".to_string(), json!(final_data));
 
         for label in group.select(&credit_selector) {
-            if let Some(id) = label.value().attr("id") {
-                if id.contains("CreditValue") {
-                    let text = label.text().collect::<Vec<_>>().join("").trim().to_string();
-                    semester.insert("credits".to_string(), json!(text));
-                }
+            if let Some(id) = label.value().attr("id")
+                && id.contains("CreditValue") {
+                let text = label.text().collect::<Vec<_>>().join("").trim().to_string();
+                semester.insert("credits".to_string(), json!(text));
             }
         }
 
@@ -511,3 +764,505 @@ pub fn extract_rank(html: &str) -> HashMap<String, Value> {
 
     rank_info
 }
+
+pub fn extract_progress_typed(html: &str) -> Vec<ProgressEntry> {
+    let document = Html::parse_document(html);
+    let table_selector = Selector::parse("table.sg-asp-table").unwrap();
+
+    let mut entries = Vec::new();
+
+    for table in document.select(&table_selector) {
+        let table = HeaderTable::parse(table);
+        let mark_column = ["mark", "progress", "grade", "average"]
+            .into_iter()
+            .find(|h| table.column(h).is_some());
+
+        let mark_column = match mark_column {
+            Some(h) => h,
+            None => continue,
+        };
+
+        for row in &table.rows {
+            let course = table.text(row, "course");
+            if course.is_empty() {
+                continue;
+            }
+
+            let comments = table
+                .headers
+                .iter()
+                .enumerate()
+                .filter(|(_, h)| h.starts_with("comment"))
+                .filter_map(|(i, _)| row.get(i).map(|&c| clean_cell_text(c)))
+                .filter(|c| !c.is_empty())
+                .collect();
+
+            entries.push(ProgressEntry {
+                course,
+                description: table.text(row, "description"),
+                period: table.text(row, "period"),
+                teacher: table.text(row, "teacher"),
+                room: table.text(row, "room"),
+                mark: Grade::parse(&table.text(row, mark_column)),
+                comments,
+            });
+        }
+    }
+
+    entries
+}
+
+enum ReportCardColumn {
+    Course,
+    Description,
+    Period,
+    Teacher,
+    Room,
+    AttemptedCredit,
+    EarnedCredit,
+    GradingPeriod,
+    Exam,
+    Semester,
+    Conduct,
+    Comment,
+    Other,
+}
+
+fn report_card_column(header: &str) -> ReportCardColumn {
+    let compact: String = header.chars().filter(|c| c.is_alphanumeric()).collect();
+    let starts_with_any = |prefixes: &[&str]| prefixes.iter().any(|p| compact.starts_with(p));
+
+    match compact.as_str() {
+        "course" => ReportCardColumn::Course,
+        "description" => ReportCardColumn::Description,
+        "period" | "per" => ReportCardColumn::Period,
+        "teacher" => ReportCardColumn::Teacher,
+        "room" => ReportCardColumn::Room,
+        "attcredit" | "attemptedcredit" => ReportCardColumn::AttemptedCredit,
+        "erncredit" | "earnedcredit" => ReportCardColumn::EarnedCredit,
+        _ if starts_with_any(&["exm", "exam"]) => ReportCardColumn::Exam,
+        _ if starts_with_any(&["sem", "avg"]) => ReportCardColumn::Semester,
+        _ if starts_with_any(&["cnd", "conduct"]) => ReportCardColumn::Conduct,
+        _ if starts_with_any(&["cmt", "comment"]) => ReportCardColumn::Comment,
+        _ if compact.starts_with(|c: char| c.is_ascii_digit())
+            || (compact.len() <= 3 && compact.ends_with(|c: char| c.is_ascii_digit())) =>
+        {
+            ReportCardColumn::GradingPeriod
+        }
+        _ => ReportCardColumn::Other,
+    }
+}
+
+pub fn extract_report_cards_typed(html: &str) -> Vec<ReportCardEntry> {
+    let document = Html::parse_document(html);
+    let table_selector = Selector::parse("table.sg-asp-table").unwrap();
+
+    let mut entries = Vec::new();
+
+    for table in document.select(&table_selector) {
+        let table = HeaderTable::parse(table);
+        if table.column("course").is_none() || table.column("description").is_none() {
+            continue;
+        }
+
+        let columns: Vec<ReportCardColumn> = table.headers.iter().map(|h| report_card_column(h)).collect();
+
+        for row in &table.rows {
+            let mut entry = ReportCardEntry {
+                course: String::new(),
+                description: String::new(),
+                period: String::new(),
+                teacher: String::new(),
+                room: String::new(),
+                attempted_credit: None,
+                earned_credit: None,
+                grades: IndexMap::new(),
+                exams: IndexMap::new(),
+                semesters: IndexMap::new(),
+                conduct: IndexMap::new(),
+                comments: Vec::new(),
+                other: IndexMap::new(),
+            };
+
+            for ((column, label), cell) in columns.iter().zip(&table.labels).zip(row) {
+                let text = clean_cell_text(*cell);
+                let label = label.clone();
+
+                match column {
+                    ReportCardColumn::Course => entry.course = text,
+                    ReportCardColumn::Description => entry.description = text,
+                    ReportCardColumn::Period => entry.period = text,
+                    ReportCardColumn::Teacher => entry.teacher = text,
+                    ReportCardColumn::Room => entry.room = text,
+                    ReportCardColumn::AttemptedCredit => entry.attempted_credit = parse_number(&text),
+                    ReportCardColumn::EarnedCredit => entry.earned_credit = parse_number(&text),
+                    ReportCardColumn::GradingPeriod => {
+                        entry.grades.insert(label, Grade::parse(&text));
+                    }
+                    ReportCardColumn::Exam => {
+                        entry.exams.insert(label, Grade::parse(&text));
+                    }
+                    ReportCardColumn::Semester => {
+                        entry.semesters.insert(label, Grade::parse(&text));
+                    }
+                    ReportCardColumn::Conduct => {
+                        entry.conduct.insert(label, text);
+                    }
+                    ReportCardColumn::Comment => {
+                        if !text.is_empty() {
+                            entry.comments.push(text);
+                        }
+                    }
+                    ReportCardColumn::Other => {
+                        if !text.is_empty() {
+                            entry.other.insert(label, text);
+                        }
+                    }
+                }
+            }
+
+            if !entry.course.is_empty() {
+                entries.push(entry);
+            }
+        }
+    }
+
+    entries
+}
+
+pub fn extract_dropdown(html: &str, id_fragment: &str) -> Option<(String, Vec<DropdownOption>)> {
+    let document = Html::parse_document(html);
+    let select_selector = Selector::parse("select[name]").unwrap();
+    let option_selector = Selector::parse("option").unwrap();
+
+    let select = document.select(&select_selector).find(|s| {
+        s.value().attr("id").is_some_and(|id| id.contains(id_fragment))
+            || s.value().attr("name").is_some_and(|name| name.contains(id_fragment))
+    })?;
+
+    let options = select
+        .select(&option_selector)
+        .map(|option| {
+            let label = clean_cell_text(option);
+            DropdownOption {
+                value: option.value().attr("value").map(str::to_string).unwrap_or_else(|| label.clone()),
+                label,
+                selected: option.value().attr("selected").is_some(),
+            }
+        })
+        .collect();
+
+    Some((select.value().attr("name").unwrap_or_default().to_string(), options))
+}
+
+pub fn extract_report_card_runs(html: &str) -> Vec<DropdownOption> {
+    extract_dropdown(html, "ddlRCRuns").map(|(_, options)| options).unwrap_or_default()
+}
+
+pub fn extract_progress_dates(html: &str) -> Vec<DropdownOption> {
+    extract_dropdown(html, "ddlIPRDates").map(|(_, options)| options).unwrap_or_default()
+}
+
+fn parse_transcript_courses(table: ElementRef) -> Vec<TranscriptCourse> {
+    let table = HeaderTable::parse(table);
+    let grade_column = table
+        .headers
+        .iter()
+        .find(|h| h.starts_with("sem") || h.as_str() == "grade" || h.as_str() == "mark")
+        .cloned()
+        .unwrap_or_default();
+    let credit_column = table
+        .headers
+        .iter()
+        .find(|h| h.starts_with("credit") || h.as_str() == "crd")
+        .cloned()
+        .unwrap_or_default();
+
+    table
+        .rows
+        .iter()
+        .map(|row| TranscriptCourse {
+            code: table.text(row, "course"),
+            description: table.text(row, "description"),
+            grade: Grade::parse(&table.text(row, &grade_column)),
+            credit: parse_number(&table.text(row, &credit_column)),
+        })
+        .filter(|c| !c.code.is_empty())
+        .collect()
+}
+
+fn semester_sort_key(semester: &Semester) -> (u32, u32) {
+    let year = semester
+        .year
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|part| !part.is_empty())
+        .and_then(|part| part.parse().ok())
+        .unwrap_or(u32::MAX);
+    let term = semester.semester.trim().parse().unwrap_or(u32::MAX);
+    (year, term)
+}
+
+pub fn extract_transcript_typed(html: &str) -> Transcript {
+    let document = Html::parse_document(html);
+    let group_selector = Selector::parse("td.sg-transcript-group").unwrap();
+    let span_selector = Selector::parse("span[id]").unwrap();
+    let table_selector = Selector::parse("table.sg-asp-table").unwrap();
+    let credit_selector = Selector::parse("label[id*='CreditValue']").unwrap();
+
+    let mut semesters = Vec::new();
+
+    for group in document.select(&group_selector) {
+        let span_text = |fragment: &str| {
+            group
+                .select(&span_selector)
+                .find(|s| s.value().attr("id").is_some_and(|id| id.contains(fragment)))
+                .map(clean_cell_text)
+                .unwrap_or_default()
+        };
+
+        semesters.push(Semester {
+            year: span_text("YearValue"),
+            semester: span_text("GroupValue"),
+            grade_level: span_text("GradeValue"),
+            school: span_text("BuildingValue"),
+            courses: group.select(&table_selector).flat_map(parse_transcript_courses).collect(),
+            credits: group
+                .select(&credit_selector)
+                .next()
+                .and_then(|label| parse_number(&clean_cell_text(label))),
+        });
+    }
+
+    semesters.sort_by_key(semester_sort_key);
+
+    let gpa_selector = Selector::parse(
+        "table#plnMain_rpTranscriptGroup_tblCumGPAInfo tbody > tr.sg-asp-table-data-row"
+    ).unwrap();
+
+    let mut transcript = Transcript {
+        semesters,
+        cumulative_gpa: Vec::new(),
+        rank: None,
+        quartile: None,
+    };
+
+    for row in document.select(&gpa_selector) {
+        let mut description = String::new();
+        let mut gpa = None;
+
+        for span in row.select(&span_selector) {
+            let id = span.value().attr("id").unwrap_or_default();
+            let text = clean_cell_text(span);
+            if id.contains("GPADescr") {
+                description = text;
+            } else if id.contains("GPACum") {
+                gpa = parse_number(&text);
+            } else if id.contains("GPARank") && !text.is_empty() {
+                transcript.rank = Some(text);
+            } else if id.contains("GPAQuartile") && !text.is_empty() {
+                transcript.quartile = Some(text);
+            }
+        }
+
+        if !description.is_empty() {
+            transcript.cumulative_gpa.push(GpaEntry { description, gpa });
+        }
+    }
+
+    transcript
+}
+
+pub fn extract_schedule(html: &str) -> Vec<ScheduleEntry> {
+    let document = Html::parse_document(html);
+    let table_selector = Selector::parse("table.sg-asp-table").unwrap();
+    let mailto_selector = Selector::parse("a[href^='mailto:']").unwrap();
+
+    let mut entries = Vec::new();
+
+    for table in document.select(&table_selector) {
+        let table = HeaderTable::parse(table);
+        if table.column("course").is_none() || table.column("teacher").is_none() {
+            continue;
+        }
+
+        let first_text = |row: &[ElementRef], headers: &[&str]| {
+            headers
+                .iter()
+                .find(|h| table.column(h).is_some())
+                .map(|h| table.text(row, h))
+                .unwrap_or_default()
+        };
+
+        for row in &table.rows {
+            let course = table.text(row, "course");
+            if course.is_empty() {
+                continue;
+            }
+
+            let teacher_email = table
+                .cell(row, "teacher")
+                .and_then(|cell| cell.select(&mailto_selector).next())
+                .and_then(|a| a.value().attr("href"))
+                .map(|href| href.trim_start_matches("mailto:").trim().to_string())
+                .filter(|email| !email.is_empty());
+
+            entries.push(ScheduleEntry {
+                course,
+                description: table.text(row, "description"),
+                period: first_text(row, &["periods", "period"]),
+                teacher: table.text(row, "teacher"),
+                teacher_email,
+                room: table.text(row, "room"),
+                days: table.text(row, "days"),
+                marking_periods: first_text(row, &["marking periods", "marking period"])
+                    .split(',')
+                    .map(|mp| mp.trim().to_string())
+                    .filter(|mp| !mp.is_empty())
+                    .collect(),
+                building: table.text(row, "building"),
+                status: table.text(row, "status"),
+            });
+        }
+    }
+
+    entries
+}
+
+fn postback_call(href: &str) -> Option<(String, String)> {
+    let args = href.split("__doPostBack(").nth(1)?;
+    let mut parts = args.split('\'');
+    let target = parts.nth(1)?.to_string();
+    let argument = parts.nth(1)?.to_string();
+    Some((target, argument))
+}
+
+fn background_color(element: ElementRef) -> Option<String> {
+    let style = element.value().attr("style")?;
+    style
+        .split(';')
+        .filter_map(|rule| rule.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("background-color"))
+        .map(|(_, value)| value.trim().to_uppercase())
+}
+
+// ASP.NET calendars number days from 2000-01-01 in their postback arguments.
+pub fn calendar_day_number(date: NaiveDate) -> i64 {
+    (date - NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()).num_days()
+}
+
+fn calendar_date(day_number: i64) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(2000, 1, 1)?.checked_add_signed(chrono::Duration::days(day_number))
+}
+
+pub fn extract_calendar_target(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let link_selector = Selector::parse("table[id*='cldAttendance'] a[href*='__doPostBack']").unwrap();
+
+    document
+        .select(&link_selector)
+        .find_map(|a| postback_call(a.value().attr("href")?))
+        .map(|(target, _)| target)
+}
+
+fn extract_attendance_legend(document: &Html) -> Vec<AttendanceCode> {
+    let table_selector = Selector::parse("table[id*='Legend']").unwrap();
+
+    let mut legend = Vec::new();
+
+    for table in document.select(&table_selector) {
+        let table = HeaderTable::parse(table);
+        for row in &table.rows {
+            let code = table.text(row, "code");
+            if code.is_empty() {
+                continue;
+            }
+
+            legend.push(AttendanceCode {
+                description: table.text(row, "description"),
+                color: table.cell(row, "code").and_then(background_color),
+                code,
+            });
+        }
+    }
+
+    legend
+}
+
+pub fn extract_attendance_month(html: &str) -> Option<AttendanceMonth> {
+    let document = Html::parse_document(html);
+    let calendar_selector = Selector::parse("table[id*='cldAttendance']").unwrap();
+    let cell_selector = Selector::parse("td").unwrap();
+    let link_selector = Selector::parse("a[href*='__doPostBack']").unwrap();
+
+    let calendar = document.select(&calendar_selector).next()?;
+
+    let first_of_month = calendar.select(&cell_selector).find_map(|cell| {
+        let text = clean_cell_text(cell);
+        NaiveDate::parse_from_str(&format!("1 {}", text), "%d %B %Y").ok()
+    })?;
+
+    let legend = extract_attendance_legend(&document);
+    let describe = |code: &str| {
+        legend
+            .iter()
+            .find(|l| l.code.eq_ignore_ascii_case(code))
+            .map(|l| l.description.clone())
+            .unwrap_or_default()
+    };
+
+    let mut days = Vec::new();
+
+    for cell in calendar.select(&cell_selector) {
+        let date = cell
+            .select(&link_selector)
+            .next()
+            .and_then(|a| postback_call(a.value().attr("href")?))
+            .and_then(|(_, argument)| argument.parse().ok())
+            .and_then(calendar_date);
+
+        let date = match date {
+            Some(d) if d.year() == first_of_month.year() && d.month() == first_of_month.month() => d,
+            _ => continue,
+        };
+
+        let mut records: Vec<AttendanceRecord> = cell
+            .value()
+            .attr("title")
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let (period, code) = match line.split_once(':') {
+                    Some((period, code)) => (Some(period.trim().to_string()), code.trim().to_string()),
+                    None => (None, line.to_string()),
+                };
+                AttendanceRecord { period, description: describe(&code), code }
+            })
+            .collect();
+
+        if records.is_empty()
+            && let Some(color) = background_color(cell)
+            && let Some(entry) = legend.iter().find(|l| l.color.as_deref() == Some(color.as_str())) {
+            records.push(AttendanceRecord {
+                period: None,
+                code: entry.code.clone(),
+                description: entry.description.clone(),
+            });
+        }
+
+        if !records.is_empty() {
+            days.push(AttendanceDay {
+                date: date.format("%Y-%m-%d").to_string(),
+                records,
+            });
+        }
+    }
+
+    Some(AttendanceMonth {
+        year: first_of_month.year(),
+        month: first_of_month.month(),
+        legend,
+        days,
+    })
+}
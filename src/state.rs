@@ -0,0 +1,16 @@
+use axum::extract::FromRef;
+use std::sync::Arc;
+use crate::cache::Cache;
+use crate::districts::DistrictRegistry;
+use crate::gpa::GpaRules;
+use crate::graduation::GraduationPlan;
+use crate::webhooks::Webhooks;
+
+#[derive(Clone, FromRef)]
+pub struct AppState {
+    pub cache: Cache,
+    pub districts: Arc<DistrictRegistry>,
+    pub webhooks: Webhooks,
+    pub gpa_rules: Arc<GpaRules>,
+    pub graduation_plan: Arc<GraduationPlan>,
+}
@@ -0,0 +1,202 @@
+use reqwest::{Method, RequestBuilder, Response, Url};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::districts::District;
+use crate::error::ApiError;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Settings {
+    pub connect_timeout: Duration,
+    pub read_timeout: Duration,
+    pub request_timeout: Duration,
+    pub retry_attempts: u32,
+    pub retry_base_delay: Duration,
+    pub breaker_threshold: u32,
+    pub breaker_cooldown: Duration,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(20),
+            request_timeout: Duration::from_secs(30),
+            retry_attempts: 3,
+            retry_base_delay: Duration::from_millis(200),
+            breaker_threshold: 5,
+            breaker_cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+// Set once at startup; clients built before that (or in tests) use the defaults.
+pub fn configure(settings: Settings) {
+    let _ = SETTINGS.set(settings);
+}
+
+pub fn settings() -> Settings {
+    SETTINGS.get().copied().unwrap_or_default()
+}
+
+#[derive(Default)]
+struct Upstream {
+    requests: u64,
+    retries: u64,
+    failures: u64,
+    rejected: u64,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+const CUSTOM_UPSTREAM: &str = "custom";
+
+// Keyed by origin (scheme, host and port) for configured districts; every custom link shares
+// the CUSTOM_UPSTREAM entry, so callers can't grow the map or /metrics by making up origins.
+fn upstreams() -> &'static Mutex<BTreeMap<String, Upstream>> {
+    static UPSTREAMS: OnceLock<Mutex<BTreeMap<String, Upstream>>> = OnceLock::new();
+    UPSTREAMS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+fn district_origins() -> &'static Mutex<HashSet<String>> {
+    static ORIGINS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    ORIGINS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+// Called at startup with the configured districts.
+pub fn register_districts(districts: &[District]) {
+    let mut origins = district_origins().lock().unwrap();
+    origins.extend(
+        districts
+            .iter()
+            .filter_map(|d| Url::parse(&d.base_url).ok())
+            .map(|url| url.origin().ascii_serialization()),
+    );
+}
+
+fn upstream_key(url: &Url) -> String {
+    let origin = url.origin().ascii_serialization();
+    if district_origins().lock().unwrap().contains(&origin) {
+        origin
+    } else {
+        CUSTOM_UPSTREAM.to_string()
+    }
+}
+
+fn with_upstream<T>(origin: &str, f: impl FnOnce(&mut Upstream) -> T) -> T {
+    let mut upstreams = upstreams().lock().unwrap();
+    f(upstreams.entry(origin.to_string()).or_default())
+}
+
+fn check_breaker(origin: &str) -> Result<(), ApiError> {
+    with_upstream(origin, |upstream| match upstream.open_until {
+        Some(until) if Instant::now() < until => {
+            upstream.rejected += 1;
+            Err(ApiError::CircuitOpen {
+                upstream: origin.to_string(),
+                retry_after: until.saturating_duration_since(Instant::now()).as_secs().max(1),
+            })
+        }
+        _ => {
+            upstream.requests += 1;
+            Ok(())
+        }
+    })
+}
+
+// Once the cooldown passes requests go through again, but the next failure re-opens the breaker.
+fn record(origin: &str, success: bool) {
+    let settings = settings();
+    with_upstream(origin, |upstream| {
+        if success {
+            upstream.consecutive_failures = 0;
+            upstream.open_until = None;
+            return;
+        }
+
+        upstream.failures += 1;
+        upstream.consecutive_failures += 1;
+        if upstream.consecutive_failures >= settings.breaker_threshold {
+            upstream.open_until = Some(Instant::now() + settings.breaker_cooldown);
+        }
+    });
+}
+
+fn backoff(retry: u32) -> Duration {
+    let base = settings().retry_base_delay * 2u32.pow(retry.saturating_sub(1).min(10));
+    base.mul_f64(rand::random_range(0.5..1.5))
+}
+
+fn transient(result: &Result<Response, reqwest::Error>) -> bool {
+    match result {
+        Ok(response) => response.status().is_server_error(),
+        Err(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+    }
+}
+
+// Sends through the district's circuit breaker. GETs are retried on network errors and 5xx
+// responses; postbacks are sent once since ASP.NET may have acted on them.
+pub async fn send(request: RequestBuilder, context: &str) -> Result<Response, ApiError> {
+    let (client, request) = request.build_split();
+    let request = request.map_err(|e| ApiError::Internal(format!("{}: {}", context, e)))?;
+    let origin = upstream_key(request.url());
+
+    check_breaker(&origin)?;
+
+    let attempts = if matches!(*request.method(), Method::GET | Method::HEAD) {
+        settings().retry_attempts.max(1)
+    } else {
+        1
+    };
+
+    let mut attempt = 1;
+    let result = loop {
+        let result = match request.try_clone() {
+            Some(retryable) if attempt < attempts => client.execute(retryable).await,
+            _ => break client.execute(request).await,
+        };
+
+        if !transient(&result) {
+            break result;
+        }
+
+        with_upstream(&origin, |upstream| upstream.retries += 1);
+        tokio::time::sleep(backoff(attempt)).await;
+        attempt += 1;
+    };
+
+    record(&origin, !transient(&result));
+    result.map_err(|e| ApiError::upstream(context, e))
+}
+
+type Series = (&'static str, &'static str, &'static str, fn(&Upstream, Instant) -> u64);
+
+// Prometheus text format.
+pub fn metrics() -> String {
+    let upstreams = upstreams().lock().unwrap();
+    let now = Instant::now();
+    let mut out = String::new();
+
+    let series: [Series; 5] = [
+        ("hac_upstream_requests_total", "counter", "Requests sent to Home Access Center", |u, _| u.requests),
+        ("hac_upstream_retries_total", "counter", "Retried upstream attempts", |u, _| u.retries),
+        ("hac_upstream_failures_total", "counter", "Requests that failed after retries", |u, _| u.failures),
+        ("hac_upstream_rejected_total", "counter", "Requests failed fast by an open circuit breaker", |u, _| u.rejected),
+        ("hac_upstream_circuit_open", "gauge", "1 while the circuit breaker is open", |u, now| {
+            u.open_until.is_some_and(|until| now < until) as u64
+        }),
+    ];
+
+    for (name, kind, help, value) in series {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        for (origin, upstream) in upstreams.iter() {
+            let _ = writeln!(out, "{}{{upstream=\"{}\"}} {}", name, origin, value(upstream, now));
+        }
+    }
+
+    out
+}
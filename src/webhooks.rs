@@ -0,0 +1,434 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{header, Client, StatusCode, Url};
+use serde::Serialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use crate::cache::Cache;
+use crate::changes::{self, ChangeKind, GradeChange};
+use crate::districts::District;
+use crate::error::ApiError;
+use crate::fetchers::fetch_assignments_page;
+use crate::handlers::{with_relogin, Credentials};
+use crate::models::ClassGrades;
+use crate::scraping::extract_gradebook_typed;
+
+pub const SIGNATURE_HEADER: &str = "X-HAC-Signature";
+pub const EVENT_HEADER: &str = "X-HAC-Event";
+pub const DELIVERY_HEADER: &str = "X-HAC-Delivery";
+
+const EVENT: &str = "grade.changed";
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Settings {
+    pub attempts: u32,
+    pub backoff: Duration,
+    pub subscription_ttl: Duration,
+    pub allow_private_urls: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            attempts: 5,
+            backoff: Duration::from_secs(30),
+            subscription_ttl: Duration::from_secs(7 * 24 * 60 * 60),
+            allow_private_urls: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Subscription {
+    id: String,
+    username: String,
+    password: String,
+    district: District,
+    url: String,
+    secret: String,
+    short: bool,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    active: bool,
+    delivering: bool,
+    baseline: Option<HashMap<String, ClassGrades>>,
+}
+
+impl Subscription {
+    fn info(&self) -> SubscriptionInfo {
+        SubscriptionInfo {
+            id: self.id.clone(),
+            url: self.url.clone(),
+            short: self.short,
+            active: self.active,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+        }
+    }
+
+    // Keeps the subscription listed so its owner can see why deliveries stopped, but forgets the password.
+    fn disable(&mut self) {
+        self.active = false;
+        self.password.clear();
+        self.baseline = None;
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SubscriptionInfo {
+    pub id: String,
+    pub url: String,
+    pub short: bool,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    id: &'a str,
+    event: &'static str,
+    subscription: &'a str,
+    change: &'a GradeChange,
+    sent_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct Webhooks {
+    subscriptions: Arc<RwLock<HashMap<String, Subscription>>>,
+    client: Client,
+    settings: Settings,
+}
+
+fn random_hex(len: usize) -> String {
+    (0..len).map(|_| format!("{:02x}", rand::random::<u8>())).collect()
+}
+
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    let digest: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", digest)
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public(IpAddr::V4(v4)),
+            None => {
+                let first = v6.segments()[0];
+                !(v6.is_loopback() || v6.is_unspecified() || v6.is_multicast() || first & 0xfe00 == 0xfc00 || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+// Webhook urls come from users, so unless the operator opts in they may only reach public addresses.
+// Checked again before every delivery since DNS can change after subscribing.
+async fn check_destination(url: &Url, allow_private: bool) -> Result<(), String> {
+    if allow_private {
+        return Ok(());
+    }
+
+    let host = url.host_str().ok_or("has no host")?.trim_matches(['[', ']']);
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<IpAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("does not resolve: {}", e))?
+        .map(|addr| addr.ip())
+        .collect();
+
+    match addrs.iter().find(|ip| !is_public(**ip)) {
+        _ if addrs.is_empty() => Err("does not resolve".to_string()),
+        Some(ip) => Err(format!("points at non-public address {}", ip)),
+        None => Ok(()),
+    }
+}
+
+// The delivery client resolves hosts through this too, so a name that passed check_destination
+// can't be re-pointed at a private address by the time the request connects.
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+// Scores showing up or moving are worth a notification; reshuffled classes and removed rows are not.
+fn is_grade_change(change: &GradeChange) -> bool {
+    match change.kind {
+        ChangeKind::NewAssignment => change.new.is_some(),
+        ChangeKind::ScoreChanged | ChangeKind::AverageChanged => true,
+        _ => false,
+    }
+}
+
+impl Webhooks {
+    pub fn new(settings: Settings) -> Self {
+        let mut client = Client::builder().redirect(reqwest::redirect::Policy::none());
+        if !settings.allow_private_urls {
+            client = client.dns_resolver(Arc::new(PublicResolver));
+        }
+
+        Self {
+            subscriptions: Arc::new(RwLock::new(HashMap::new())),
+            client: client.build().expect("default TLS backend is available"),
+            settings: Settings {
+                attempts: settings.attempts.max(1),
+                ..settings
+            },
+        }
+    }
+
+    pub async fn subscribe(
+        &self,
+        username: &str,
+        password: &str,
+        district: &District,
+        url: &str,
+        secret: Option<String>,
+        short: bool,
+    ) -> Result<(SubscriptionInfo, String), ApiError> {
+        let parsed = Url::parse(url).map_err(|e| ApiError::BadRequest(format!("Invalid webhook url '{}': {}", url, e)))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(ApiError::BadRequest(format!("Webhook url '{}' must use http or https", url)));
+        }
+        check_destination(&parsed, self.settings.allow_private_urls)
+            .await
+            .map_err(|e| ApiError::BadRequest(format!("Webhook url '{}' {}", url, e)))?;
+
+        let secret = match secret {
+            Some(s) if s.trim().is_empty() => return Err(ApiError::BadRequest("Webhook secret must not be empty".to_string())),
+            Some(s) => s,
+            None => random_hex(32),
+        };
+
+        let subscription = Subscription {
+            id: random_hex(16),
+            username: username.to_string(),
+            password: password.to_string(),
+            district: district.clone(),
+            url: parsed.to_string(),
+            secret: secret.clone(),
+            short,
+            created_at: Utc::now(),
+            expires_at: Utc::now() + self.settings.subscription_ttl,
+            active: true,
+            delivering: false,
+            baseline: None,
+        };
+        let info = subscription.info();
+
+        let mut subscriptions = self.subscriptions.write().await;
+        subscriptions.insert(subscription.id.clone(), subscription);
+        Ok((info, secret))
+    }
+
+    pub async fn list(&self, username: &str, link: &str) -> Vec<SubscriptionInfo> {
+        let subscriptions = self.subscriptions.read().await;
+        let mut list: Vec<SubscriptionInfo> = subscriptions
+            .values()
+            .filter(|s| s.username == username && s.district.base_url == link)
+            .map(Subscription::info)
+            .collect();
+        list.sort_by_key(|s| s.created_at);
+        list
+    }
+
+    pub async fn unsubscribe(&self, username: &str, link: &str, id: &str) -> Result<(), ApiError> {
+        let mut subscriptions = self.subscriptions.write().await;
+        match subscriptions.get(id) {
+            Some(s) if s.username == username && s.district.base_url == link => {
+                subscriptions.remove(id);
+                Ok(())
+            }
+            _ => Err(ApiError::NotFound(format!("No subscription '{}'", id))),
+        }
+    }
+
+    async fn deliver(&self, subscription: &Subscription, change: &GradeChange) -> Result<(), String> {
+        let url = Url::parse(&subscription.url).map_err(|e| e.to_string())?;
+        check_destination(&url, self.settings.allow_private_urls)
+            .await
+            .map_err(|e| format!("{} {}", subscription.url, e))?;
+
+        let id = random_hex(16);
+        let body = serde_json::to_vec(&Payload {
+            id: &id,
+            event: EVENT,
+            subscription: &subscription.id,
+            change,
+            sent_at: Utc::now(),
+        })
+        .map_err(|e| e.to_string())?;
+        let signature = sign(&subscription.secret, &body);
+
+        let mut last_error = String::new();
+        for attempt in 0..self.settings.attempts {
+            if attempt > 0 {
+                tokio::time::sleep(self.settings.backoff * 2u32.pow(attempt - 1)).await;
+            }
+
+            let result = self
+                .client
+                .post(&subscription.url)
+                .timeout(DELIVERY_TIMEOUT)
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, &signature)
+                .header(EVENT_HEADER, EVENT)
+                .header(DELIVERY_HEADER, &id)
+                .body(body.clone())
+                .send()
+                .await;
+
+            match result {
+                Ok(resp) if resp.status().is_success() => return Ok(()),
+                Ok(resp) if resp.status().is_client_error() && resp.status() != StatusCode::TOO_MANY_REQUESTS => {
+                    return Err(format!("{} rejected delivery {} with {}", subscription.url, id, resp.status()));
+                }
+                Ok(resp) => last_error = format!("{} returned {} for delivery {}", subscription.url, resp.status(), id),
+                Err(e) => last_error = format!("Failed to deliver {} to {}: {}", id, subscription.url, e),
+            }
+        }
+
+        Err(last_error)
+    }
+
+    // The baseline only moves once every change went out, so after a failure the next poll sends the
+    // remaining changes again; receivers can see a change more than once.
+    async fn deliver_changes(
+        &self,
+        subscription: Subscription,
+        grade_changes: Vec<GradeChange>,
+        gradebook: HashMap<String, ClassGrades>,
+    ) -> usize {
+        let mut delivered = 0;
+        for change in &grade_changes {
+            match self.deliver(&subscription, change).await {
+                Ok(()) => delivered += 1,
+                Err(e) => {
+                    tracing::warn!("Webhook {} delivery failed: {}", subscription.id, e);
+                    break;
+                }
+            }
+        }
+
+        let mut stored = self.subscriptions.write().await;
+        if let Some(current) = stored.get_mut(&subscription.id) {
+            current.delivering = false;
+            if delivered == grade_changes.len() {
+                current.baseline = Some(gradebook);
+            }
+        }
+        delivered
+    }
+}
+
+// One fetch per user; the first poll after subscribing only records the baseline. Deliveries run in
+// their own tasks so a slow receiver can't hold up the next poll; the returned handles resolve to the
+// number of payloads each one delivered.
+pub async fn poll_once(cache: &Cache, webhooks: &Webhooks) -> Vec<JoinHandle<usize>> {
+    let now = Utc::now();
+    let mut users: HashMap<(String, String), Vec<Subscription>> = HashMap::new();
+    {
+        let mut stored = webhooks.subscriptions.write().await;
+        stored.retain(|_, s| s.expires_at > now);
+        for subscription in stored.values().filter(|s| s.active && !s.delivering) {
+            users
+                .entry((subscription.username.clone(), subscription.district.base_url.clone()))
+                .or_default()
+                .push(subscription.clone());
+        }
+    }
+
+    let mut deliveries = Vec::new();
+
+    for subscriptions in users.into_values() {
+        let creds = Credentials {
+            username: subscriptions[0].username.clone(),
+            password: subscriptions[0].password.clone(),
+            district: subscriptions[0].district.clone(),
+        };
+        let (creds_ref, cache_ref) = (&creds, cache);
+
+        let html = with_relogin(cache, &creds, false, move |client| async move {
+            fetch_assignments_page(&client, creds_ref.link(), cache_ref, &creds_ref.username, true).await
+        })
+        .await;
+
+        let html = match html {
+            Ok(html) => html,
+            // Polling with a stale password every interval could lock the student out of HAC.
+            Err(ApiError::InvalidCredentials) => {
+                tracing::warn!("HAC rejected the stored password for {}, disabling its webhooks", creds.username);
+                let mut stored = webhooks.subscriptions.write().await;
+                for subscription in &subscriptions {
+                    if let Some(current) = stored.get_mut(&subscription.id) {
+                        current.disable();
+                    }
+                }
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!("Webhook poll for {} failed: {}", creds.username, e);
+                continue;
+            }
+        };
+        let gradebook = extract_gradebook_typed(&html, false);
+
+        let mut stored = webhooks.subscriptions.write().await;
+        for subscription in subscriptions {
+            let Some(current) = stored.get_mut(&subscription.id) else {
+                continue;
+            };
+            let Some(baseline) = &current.baseline else {
+                current.baseline = Some(gradebook.clone());
+                continue;
+            };
+            let grade_changes: Vec<GradeChange> = changes::diff(baseline, &gradebook, current.short)
+                .into_iter()
+                .filter(is_grade_change)
+                .collect();
+
+            if grade_changes.is_empty() {
+                current.baseline = Some(gradebook.clone());
+                continue;
+            }
+
+            current.delivering = true;
+            let (webhooks, subscription, gradebook) = (webhooks.clone(), current.clone(), gradebook.clone());
+            deliveries.push(tokio::spawn(async move {
+                webhooks.deliver_changes(subscription, grade_changes, gradebook).await
+            }));
+        }
+    }
+
+    deliveries
+}
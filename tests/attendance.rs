@@ -0,0 +1,38 @@
+use chrono::NaiveDate;
+use hac_api::attendance::{date_range, months_between};
+use hac_api::error::ApiError;
+
+fn date(value: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+}
+
+#[test]
+fn range_defaults_to_month_so_far() {
+    let today = date("2025-10-21");
+    assert_eq!(date_range(None, None, today).unwrap(), (date("2025-10-01"), today));
+    assert_eq!(date_range(Some("2025-09-15"), None, today).unwrap(), (date("2025-09-15"), today));
+}
+
+#[test]
+fn range_is_limited_to_twelve_months() {
+    let today = date("2025-10-21");
+    assert!(date_range(Some("2024-11-30"), Some("2025-10-01"), today).is_ok());
+
+    let too_long = date_range(Some("2024-10-31"), Some("2025-10-01"), today).unwrap_err();
+    assert!(matches!(too_long, ApiError::BadRequest(ref e) if e.contains("12 months")), "{:?}", too_long);
+
+    // Rejected from the month arithmetic alone, before any month list is built.
+    assert!(matches!(date_range(Some("0001-01-01"), Some("9999-12-31"), today), Err(ApiError::BadRequest(_))));
+    assert!(matches!(date_range(Some("2025-11-01"), Some("2025-10-01"), today), Err(ApiError::BadRequest(_))));
+}
+
+#[test]
+fn months_stop_at_the_end_of_the_calendar() {
+    let (from, to) = date_range(Some("+262142-12-01"), Some("+262142-12-31"), date("2025-10-21")).unwrap();
+    assert_eq!(months_between(from, to), [from]);
+
+    assert_eq!(
+        months_between(date("2025-09-15"), date("2025-11-02")),
+        [date("2025-09-01"), date("2025-10-01"), date("2025-11-01")]
+    );
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::fixture;
+use hac_api::calculator::{find_class, what_if, WhatIfRequest, WhatIfResult};
+use hac_api::error::ApiError;
+use hac_api::scraping::extract_gradebook_typed;
+use serde_json::{json, Value};
+
+// ENGLISH 2 AP in the fixture: Major 92/100 plus an ungraded Unit 3 Test, Minor
+// 97/100 plus a missing assignment, and Other holding only an exempt Reading Log.
+fn run(request: Value) -> Result<WhatIfResult, ApiError> {
+    let gradebook = extract_gradebook_typed(&fixture("Assignments.aspx"), false);
+    let request: WhatIfRequest = serde_json::from_value(request).unwrap();
+    let (name, class) = find_class(&gradebook, &request.class)?;
+    what_if(name, class, &request)
+}
+
+fn percent(result: &WhatIfResult, category: &str) -> Option<f64> {
+    result.categories.iter().find(|c| c.name == category).unwrap().percent
+}
+
+#[test]
+fn unchanged_class_matches_its_categories() {
+    let result = run(json!({ "class": "english" })).unwrap();
+
+    assert_eq!(result.class, "2301A - 1    ENGLISH 2 AP S1");
+    assert_eq!(result.reported_average.score.points(), Some(77.5));
+    // Missing counts as zero, while exempt and not graded are left out.
+    assert_eq!(percent(&result, "Major"), Some(92.0));
+    assert_eq!(percent(&result, "Minor"), Some(48.5));
+    assert_eq!(percent(&result, "Other"), None);
+    // Other has no graded work, so Major and Minor are reweighted over 90.
+    assert_eq!(result.current_average, Some(77.5));
+    assert_eq!(result.projected_average, result.current_average);
+}
+
+#[test]
+fn unchanged_classes_reproduce_the_reported_average() {
+    for class in ["english", "calculus"] {
+        let result = run(json!({ "class": class })).unwrap();
+        assert_eq!(result.current_average, result.reported_average.score.points(), "{}", result.class);
+    }
+}
+
+#[test]
+fn edits_drops_and_additions() {
+    let edited = run(json!({ "class": "english", "edits": [{ "name": "unit 3 test", "score": 80 }] })).unwrap();
+    assert_eq!(percent(&edited, "Major"), Some(86.0));
+    assert_eq!(edited.projected_average, Some(73.5));
+    assert_eq!(edited.current_average, Some(77.5));
+
+    let rescaled = run(json!({ "class": "english", "edits": [{ "name": "Unit 3 Test", "score": 45, "total_points": 50 }] })).unwrap();
+    assert_eq!(percent(&rescaled, "Major"), Some(91.33));
+
+    let dropped = run(json!({ "class": "english", "drops": ["Socratic Seminar Prep"] })).unwrap();
+    assert_eq!(percent(&dropped, "Minor"), Some(97.0));
+    assert_eq!(dropped.projected_average, Some(93.67));
+
+    let added = run(json!({ "class": "english", "additions": [{ "category": "other", "score": 9, "total_points": 10 }] })).unwrap();
+    assert_eq!(percent(&added, "Other"), Some(90.0));
+    assert_eq!(added.projected_average, Some(78.75));
+
+    let weighted = run(json!({ "class": "english", "additions": [{ "category": "Major", "score": 100, "weight": 2 }] })).unwrap();
+    let major = weighted.categories.iter().find(|c| c.name == "Major").unwrap();
+    assert_eq!((major.points, major.max_points), (292.0, 300.0));
+}
+
+#[test]
+fn exempt_assignments_count_once_edited() {
+    let result = run(json!({ "class": "english", "edits": [{ "name": "Reading Log", "score": 5 }] })).unwrap();
+    assert_eq!(percent(&result, "Other"), Some(50.0));
+    assert_eq!(result.projected_average, Some(74.75));
+}
+
+#[test]
+fn unknown_names_are_errors() {
+    assert!(matches!(run(json!({ "class": "chemistry" })), Err(ApiError::NotFound(_))));
+    assert!(matches!(run(json!({ "class": "S1" })), Err(ApiError::BadRequest(ref e)) if e.contains("More than one")));
+
+    let edit = run(json!({ "class": "english", "edits": [{ "name": "Final Exam", "score": 90 }] }));
+    assert!(matches!(edit, Err(ApiError::BadRequest(ref e)) if e.contains("Final Exam")));
+
+    let drop = run(json!({ "class": "english", "drops": ["Final Exam"] }));
+    assert!(matches!(drop, Err(ApiError::BadRequest(ref e)) if e.contains("Final Exam")));
+
+    let addition = run(json!({ "class": "english", "additions": [{ "category": "Labs", "score": 90 }] }));
+    assert!(matches!(addition, Err(ApiError::BadRequest(ref e)) if e.contains("Labs")));
+}
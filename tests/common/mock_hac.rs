@@ -0,0 +1,333 @@
+use axum::{
+    extract::{Form, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use super::{fixture, serve};
+
+pub const USERNAME: &str = "student";
+pub const PASSWORD: &str = "hunter2";
+
+const FORM_TOKEN: &str = "mock-form-token";
+const ANTIFORGERY_COOKIE: &str = "__RequestVerificationToken_L0hvbWVBY2Nlc3M1=mock-cookie-token";
+const SESSION_COOKIE: &str = "ASP.NET_SessionId";
+
+#[derive(Default)]
+struct MockState {
+    logins: usize,
+    next_session: usize,
+    sessions: HashSet<String>,
+    postbacks: Vec<(String, HashMap<String, String>)>,
+    page_hits: HashMap<String, usize>,
+    overrides: HashMap<String, String>,
+    failures: usize,
+    down: bool,
+    latency: Duration,
+    logon_error: Option<StatusCode>,
+    password: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct MockHac {
+    pub base_url: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockHac {
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        let app = Router::new()
+            .route("/HomeAccess/Account/LogOn", get(logon_form).post(logon))
+            .route("/HomeAccess/Home", get(home))
+            .route("/HomeAccess/Classes/Classwork", get(classwork))
+            .route("/HomeAccess/Content/Student/{page}", get(student_page).post(student_postback))
+            .route("/HomeAccess/Content/Attendance/MonthlyView.aspx", get(attendance).post(attendance_postback))
+            .with_state(state.clone());
+
+        Self {
+            base_url: serve(app).await,
+            state,
+        }
+    }
+
+    pub fn logins(&self) -> usize {
+        self.state.lock().unwrap().logins
+    }
+
+    pub fn postbacks(&self) -> Vec<(String, HashMap<String, String>)> {
+        self.state.lock().unwrap().postbacks.clone()
+    }
+
+    pub fn page_hits(&self, page: &str) -> usize {
+        self.state.lock().unwrap().page_hits.get(page).copied().unwrap_or(0)
+    }
+
+    // Serves `html` in place of the fixture for `page`, e.g. to simulate a teacher posting grades.
+    pub fn set_page(&self, page: &str, html: String) {
+        self.state.lock().unwrap().overrides.insert(page.to_string(), html);
+    }
+
+    // Student pages answer 503 for the next `count` requests.
+    pub fn fail_next(&self, count: usize) {
+        self.state.lock().unwrap().failures = count;
+    }
+
+    pub fn set_down(&self, down: bool) {
+        self.state.lock().unwrap().down = down;
+    }
+
+    // Slows down logins and student pages so concurrent API requests overlap upstream.
+    pub fn set_latency(&self, latency: Duration) {
+        self.state.lock().unwrap().latency = latency;
+    }
+
+    // LogOn posts answer `status` instead of checking the credentials.
+    pub fn set_logon_error(&self, status: Option<StatusCode>) {
+        self.state.lock().unwrap().logon_error = status;
+    }
+
+    // Simulates the student changing their HAC password.
+    pub fn set_password(&self, password: &str) {
+        self.state.lock().unwrap().password = Some(password.to_string());
+    }
+
+    pub fn expire_sessions(&self) {
+        self.state.lock().unwrap().sessions.clear();
+    }
+}
+
+type Shared = State<Arc<Mutex<MockState>>>;
+
+fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .map(str::trim)
+        .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+}
+
+async fn delay(state: &Mutex<MockState>) {
+    let latency = state.lock().unwrap().latency;
+    tokio::time::sleep(latency).await;
+}
+
+fn has_session(state: &Mutex<MockState>, headers: &HeaderMap) -> bool {
+    cookie(headers, SESSION_COOKIE).is_some_and(|id| state.lock().unwrap().sessions.contains(id))
+}
+
+fn redirect_to_logon(return_url: &str) -> Response {
+    let location = format!("/HomeAccess/Account/LogOn?ReturnUrl={}", return_url);
+    (StatusCode::FOUND, [(header::LOCATION, location)]).into_response()
+}
+
+fn logon_page() -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<body>
+<form action="/HomeAccess/Account/LogOn?ReturnUrl=%2fHomeAccess%2f" method="post">
+<input name="__RequestVerificationToken" type="hidden" value="{}" />
+<input name="SCKTY00328510CustomEnabled" type="hidden" value="True" />
+<input name="SCKTY00436568CustomEnabled" type="hidden" value="True" />
+<select id="Database" name="Database">
+  <option selected="selected" value="10">Mock ISD</option>
+  <option value="20">Mock ISD Archive</option>
+</select>
+<input checked="checked" id="VerificationOption_0" name="VerificationOption" type="radio" value="UsernamePassword" />
+<label for="VerificationOption_0">User Name / Password</label>
+<input id="VerificationOption_1" name="VerificationOption" type="radio" value="Pin" />
+<label for="VerificationOption_1">PIN</label>
+<input id="LogOnDetails_UserName" name="LogOnDetails.UserName" type="text" value="" />
+<input id="LogOnDetails_Password" name="LogOnDetails.Password" type="password" />
+<input id="tempUN" name="tempUN" type="hidden" value="" />
+<input id="tempPW" name="tempPW" type="hidden" value="" />
+<button type="submit">Log In</button>
+</form>
+</body>
+</html>"#,
+        FORM_TOKEN
+    )
+}
+
+async fn logon_form() -> Response {
+    (
+        [(header::SET_COOKIE, format!("{}; path=/HomeAccess", ANTIFORGERY_COOKIE))],
+        Html(logon_page()),
+    )
+        .into_response()
+}
+
+async fn logon(
+    State(state): Shared,
+    headers: HeaderMap,
+    Form(form): Form<HashMap<String, String>>,
+) -> Response {
+    delay(&state).await;
+    if let Some(status) = state.lock().unwrap().logon_error {
+        return (status, "Server Error").into_response();
+    }
+    let field = |name: &str| form.get(name).map(String::as_str);
+    let antiforgery = ANTIFORGERY_COOKIE.split_once('=').unwrap();
+
+    let form_ok = field("__RequestVerificationToken") == Some(FORM_TOKEN)
+        && cookie(&headers, antiforgery.0) == Some(antiforgery.1)
+        && field("Database") == Some("10")
+        && field("VerificationOption") == Some("UsernamePassword")
+        && field("SCKTY00328510CustomEnabled") == Some("True");
+    if !form_ok {
+        return (StatusCode::BAD_REQUEST, "malformed LogOn post").into_response();
+    }
+
+    let password = state.lock().unwrap().password.clone().unwrap_or_else(|| PASSWORD.to_string());
+    if field("LogOnDetails.UserName") != Some(USERNAME) || field("LogOnDetails.Password") != Some(password.as_str()) {
+        return Html(logon_page()).into_response();
+    }
+
+    let session_id = {
+        let mut state = state.lock().unwrap();
+        state.logins += 1;
+        state.next_session += 1;
+        let id = format!("mock-session-{}", state.next_session);
+        state.sessions.insert(id.clone());
+        id
+    };
+
+    (
+        StatusCode::FOUND,
+        [
+            (header::LOCATION, "/HomeAccess/Home".to_string()),
+            (header::SET_COOKIE, format!("{}={}; path=/; HttpOnly", SESSION_COOKIE, session_id)),
+        ],
+    )
+        .into_response()
+}
+
+async fn home(State(state): Shared, headers: HeaderMap) -> Response {
+    if !has_session(&state, &headers) {
+        return redirect_to_logon("%2fHomeAccess%2fHome");
+    }
+    Html("<html><body>Home</body></html>").into_response()
+}
+
+async fn classwork(State(state): Shared, headers: HeaderMap) -> Response {
+    if !has_session(&state, &headers) {
+        return redirect_to_logon("%2fHomeAccess%2fClasses%2fClasswork");
+    }
+    Html(fixture("Classwork.html")).into_response()
+}
+
+// Like ASP.NET, the viewstate is only valid in the session that rendered the page.
+fn session_viewstate(page: &str, session: &str) -> Option<String> {
+    viewstate(page).map(|viewstate| format!("{}.{}", viewstate, session))
+}
+
+fn serve_fixture(state: &Mutex<MockState>, page: &str, session: &str) -> Response {
+    const PAGES: [&str; 6] = [
+        "Assignments.aspx",
+        "Classes.aspx",
+        "ReportCards.aspx",
+        "InterimProgress.aspx",
+        "Transcript.aspx",
+        "Registration.aspx",
+    ];
+
+    if !PAGES.contains(&page) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let mut state = state.lock().unwrap();
+    *state.page_hits.entry(page.to_string()).or_default() += 1;
+    if state.down || state.failures > 0 {
+        state.failures = state.failures.saturating_sub(1);
+        return (StatusCode::SERVICE_UNAVAILABLE, "Service Unavailable").into_response();
+    }
+    let html = state.overrides.get(page).cloned().unwrap_or_else(|| fixture(page));
+    Html(bind_viewstate(html, page, session)).into_response()
+}
+
+fn bind_viewstate(html: String, page: &str, session: &str) -> String {
+    match (viewstate(page), session_viewstate(page, session)) {
+        (Some(original), Some(bound)) => html.replace(&format!(r#"value="{}""#, original), &format!(r#"value="{}""#, bound)),
+        _ => html,
+    }
+}
+
+fn viewstate(page: &str) -> Option<String> {
+    let html = std::fs::read_to_string(super::fixture_path(page)).ok()?;
+    let start = html.find(r#"id="__VIEWSTATE" value=""#)? + r#"id="__VIEWSTATE" value=""#.len();
+    let len = html[start..].find('"')?;
+    Some(html[start..start + len].to_string())
+}
+
+async fn student_page(State(state): Shared, headers: HeaderMap, Path(page): Path<String>) -> Response {
+    delay(&state).await;
+    if !has_session(&state, &headers) {
+        return redirect_to_logon(&format!("%2fHomeAccess%2fContent%2fStudent%2f{}", page));
+    }
+    serve_fixture(&state, &page, cookie(&headers, SESSION_COOKIE).unwrap_or_default())
+}
+
+async fn student_postback(
+    State(state): Shared,
+    headers: HeaderMap,
+    Path(page): Path<String>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Response {
+    if !has_session(&state, &headers) {
+        return redirect_to_logon(&format!("%2fHomeAccess%2fContent%2fStudent%2f{}", page));
+    }
+
+    let session = cookie(&headers, SESSION_COOKIE).unwrap_or_default();
+    let expected = session_viewstate(&page, session);
+    if expected.is_none() || form.get("__VIEWSTATE") != expected.as_ref() {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Validation of viewstate MAC failed").into_response();
+    }
+
+    state.lock().unwrap().postbacks.push((page.clone(), form));
+    serve_fixture(&state, &page, session)
+}
+
+const ATTENDANCE_MONTHS: [(&str, &str); 2] = [("V9375", "MonthlyView_2025-09.aspx"), ("V9405", "MonthlyView.aspx")];
+
+async fn attendance(State(state): Shared, headers: HeaderMap) -> Response {
+    if !has_session(&state, &headers) {
+        return redirect_to_logon("%2fHomeAccess%2fContent%2fAttendance%2fMonthlyView.aspx");
+    }
+    *state.lock().unwrap().page_hits.entry("MonthlyView.aspx".to_string()).or_default() += 1;
+    let session = cookie(&headers, SESSION_COOKIE).unwrap_or_default();
+    Html(bind_viewstate(fixture("MonthlyView.aspx"), "MonthlyView.aspx", session)).into_response()
+}
+
+async fn attendance_postback(
+    State(state): Shared,
+    headers: HeaderMap,
+    Form(form): Form<HashMap<String, String>>,
+) -> Response {
+    if !has_session(&state, &headers) {
+        return redirect_to_logon("%2fHomeAccess%2fContent%2fAttendance%2fMonthlyView.aspx");
+    }
+
+    let session = cookie(&headers, SESSION_COOKIE).unwrap_or_default();
+    let viewstate_ok = ATTENDANCE_MONTHS
+        .iter()
+        .any(|(_, page)| form.get("__VIEWSTATE") == session_viewstate(page, session).as_ref());
+    let month = ATTENDANCE_MONTHS
+        .iter()
+        .find(|(argument, _)| form.get("__EVENTARGUMENT").map(String::as_str) == Some(*argument));
+
+    match month {
+        Some((_, page)) if viewstate_ok && form.get("__EVENTTARGET").map(String::as_str) == Some("ctl00$plnMain$cldAttendance") => {
+            state.lock().unwrap().postbacks.push(("MonthlyView.aspx".to_string(), form.clone()));
+            Html(bind_viewstate(fixture(page), page, session)).into_response()
+        }
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, "Invalid postback or callback argument").into_response(),
+    }
+}
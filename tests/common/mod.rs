@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+
+pub mod mock_hac;
+
+use hac_api::cache::Cache;
+use hac_api::districts::DistrictRegistry;
+use hac_api::gpa::GpaRules;
+use hac_api::graduation::GraduationPlan;
+use hac_api::routes::create_router;
+use hac_api::state::AppState;
+use hac_api::upstream;
+use hac_api::webhooks::{self, Webhooks};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn tests_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+pub fn fixture_path(name: &str) -> PathBuf {
+    tests_dir().join("fixtures").join(name)
+}
+
+pub fn fixture(name: &str) -> String {
+    let path = fixture_path(name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("missing fixture {}: {}", path.display(), e))
+}
+
+// Compares `value` against tests/snapshots/<name>.json. Run with
+// UPDATE_SNAPSHOTS=1 to (re)write the snapshot after an intended change.
+pub fn assert_snapshot<T: Serialize>(name: &str, value: &T) {
+    let actual = serde_json::to_value(value).expect("value serializes to JSON");
+    let path = tests_dir().join("snapshots").join(format!("{}.json", name));
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        let text = serde_json::to_string_pretty(&actual).unwrap() + "\n";
+        std::fs::write(&path, text).unwrap_or_else(|e| panic!("cannot write {}: {}", path.display(), e));
+        return;
+    }
+
+    let text = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!("no snapshot at {}, run with UPDATE_SNAPSHOTS=1 to create it", path.display())
+    });
+    let expected: Value = serde_json::from_str(&text).expect("snapshot is valid JSON");
+
+    assert_eq!(
+        actual,
+        expected,
+        "{} no longer matches tests/snapshots/{}.json (UPDATE_SNAPSHOTS=1 to accept)\nactual:\n{}",
+        name,
+        name,
+        serde_json::to_string_pretty(&actual).unwrap()
+    );
+}
+
+pub async fn serve(app: axum::Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+// API state with a single "mock" district pointing at `hac_url`; webhook retries back off in milliseconds.
+pub fn api_state(hac_url: &str) -> AppState {
+    let registry = json!({
+        "default": "mock",
+        "districts": [{ "id": "mock", "name": "Mock ISD", "base_url": hac_url, "database": "10" }],
+    });
+    let districts = DistrictRegistry::parse(&registry.to_string()).unwrap();
+    upstream::register_districts(districts.all());
+
+    AppState {
+        cache: Cache::new(30 * 60, 5 * 60, 24 * 60 * 60),
+        districts: Arc::new(districts),
+        webhooks: Webhooks::new(webhooks::Settings {
+            attempts: 3,
+            backoff: Duration::from_millis(10),
+            allow_private_urls: true,
+            ..webhooks::Settings::default()
+        }),
+        gpa_rules: Arc::new(GpaRules::default()),
+        graduation_plan: Arc::new(GraduationPlan::default()),
+    }
+}
+
+pub async fn spawn_api(hac_url: &str) -> String {
+    serve(create_router(api_state(hac_url))).await
+}
@@ -0,0 +1,122 @@
+use hac_api::config::Config;
+use hac_api::districts::DistrictRegistry;
+use hac_api::gpa::GpaRules;
+use hac_api::graduation::GraduationPlan;
+use std::collections::HashMap;
+
+fn load(vars: &[(&str, &str)]) -> Result<Config, String> {
+    let env: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    Config::from_sources(|name| env.get(name).cloned())
+}
+
+#[test]
+fn defaults_without_env_or_file() {
+    let config = load(&[]).unwrap();
+    assert_eq!(config, Config::default());
+    assert_eq!(config.bind, "0.0.0.0:3000");
+    assert_eq!(config.login_ttl_secs, 30 * 60);
+}
+
+#[test]
+fn env_overrides_file() {
+    let path = std::env::temp_dir().join(format!("hac-api-config-{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        "bind = \"127.0.0.1:8080\"\npage_ttl_secs = 60\nallowed_districts = [\"katyisd\"]\nlog_level = \"debug\"\n",
+    )
+    .unwrap();
+
+    let config = load(&[("HAC_CONFIG", path.to_str().unwrap()), ("HAC_PAGE_TTL", "120"), ("HAC_LOG_LEVEL", "WARN")]).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(config.bind, "127.0.0.1:8080");
+    assert_eq!(config.page_ttl_secs, 120);
+    assert_eq!(config.allowed_districts, ["katyisd"]);
+    assert_eq!(config.log_level, "warn");
+}
+
+#[test]
+fn reports_every_bad_value() {
+    let err = load(&[
+        ("HAC_BIND", "localhost"),
+        ("HAC_PAGE_TTL", "five"),
+        ("HAC_CLEANER_INTERVAL", "0"),
+        ("HAC_TLS_CERT", "/nonexistent/cert.pem"),
+        ("HAC_DEFAULT_LINK", "homeaccess.example.org"),
+        ("HAC_LOG_LEVEL", "verbose"),
+    ])
+    .unwrap_err();
+
+    assert!(err.starts_with("Invalid configuration:"));
+    for expected in [
+        "HAC_PAGE_TTL: expected a whole number of seconds, got 'five'",
+        "bind: 'localhost'",
+        "tls_cert and tls_key must be set together",
+        "cleaner_interval_secs: must be greater than 0",
+        "default_link: 'homeaccess.example.org' is not an http(s) URL",
+        "log_level: 'verbose'",
+    ] {
+        assert!(err.contains(expected), "missing '{}' in:\n{}", expected, err);
+    }
+}
+
+#[test]
+fn unknown_keys_in_file_are_rejected() {
+    let path = std::env::temp_dir().join(format!("hac-api-config-typo-{}.toml", std::process::id()));
+    std::fs::write(&path, "page_ttl = 60\n").unwrap();
+
+    let err = load(&[("HAC_CONFIG", path.to_str().unwrap())]).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert!(err.contains("unknown field `page_ttl`"), "{}", err);
+}
+
+#[test]
+fn allowed_districts_restrict_registry_and_links() {
+    let mut districts = DistrictRegistry::builtin();
+    districts.restrict(&["katyisd".to_string()]).unwrap();
+    assert!(districts.allows(&districts.for_link("https://homeaccess.katyisd.org/")));
+    assert!(!districts.allows(&districts.for_link("https://hac.example.org")));
+
+    let mut districts = DistrictRegistry::builtin();
+    assert!(districts.restrict(&["nowhere".to_string()]).unwrap_err().contains("nowhere"));
+
+    let mut districts = DistrictRegistry::builtin();
+    districts.set_default_link("https://hac.example.org/");
+    assert_eq!(districts.default_district().base_url, "https://hac.example.org");
+    assert!(districts.restrict(&["katyisd".to_string()]).unwrap_err().contains("default district 'custom'"));
+}
+
+#[test]
+fn example_files_are_valid() {
+    let root = env!("CARGO_MANIFEST_DIR");
+    let config = Config::from_file(&format!("{}/config.example.toml", root)).unwrap();
+    assert!(config.validate().is_empty(), "{:?}", config.validate());
+
+    GpaRules::load(&format!("{}/gpa_rules.example.json", root)).unwrap();
+    GraduationPlan::load(&format!("{}/graduation_plan.example.json", root)).unwrap();
+}
+
+#[test]
+fn rule_files_come_from_env_and_are_validated() {
+    let config = load(&[
+        ("HAC_DISTRICTS_FILE", "districts.json"),
+        ("HAC_GPA_RULES_FILE", "rules.json"),
+        ("HAC_GRADUATION_PLAN_FILE", " "),
+        ("HAC_WEBHOOK_ATTEMPTS", "2"),
+    ])
+    .unwrap();
+    assert_eq!(config.districts_file.as_deref(), Some("districts.json"));
+    assert_eq!(config.gpa_rules_file.as_deref(), Some("rules.json"));
+    assert_eq!(config.graduation_plan_file, None);
+    assert_eq!(config.webhook_settings().attempts, 2);
+
+    assert!(GpaRules::load("/nonexistent/rules.json").unwrap_err().contains("/nonexistent/rules.json"));
+    assert!(GpaRules::parse(r#"{ "levels": [], "bands": [] }"#).unwrap_err().contains("bands"));
+    assert!(GpaRules::parse(r#"{ "levels": [], "bands": [{ "min": 90 }] }"#).is_err());
+    assert!(GraduationPlan::parse(r#"{ "name": "Empty", "requirements": [] }"#).unwrap_err().contains("requirements"));
+    assert!(
+        GraduationPlan::parse(r#"{ "name": "Zero", "requirements": [{ "subject": "Math", "credits": 0 }] }"#)
+            .unwrap_err()
+            .contains("Math")
+    );
+}
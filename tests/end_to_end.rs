@@ -0,0 +1,933 @@
+mod common;
+
+use common::mock_hac::{MockHac, PASSWORD, USERNAME};
+use common::{api_state, fixture, serve, spawn_api};
+use hac_api::cache::Cache;
+use hac_api::gpa::GpaRules;
+use hac_api::graduation::GraduationPlan;
+use hac_api::routes::create_router;
+use hac_api::state::AppState;
+use hac_api::webhooks::{self, DELIVERY_HEADER, SIGNATURE_HEADER};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+async fn setup() -> (MockHac, String, reqwest::Client) {
+    let hac = MockHac::start().await;
+    let api = spawn_api(&hac.base_url).await;
+    (hac, api, reqwest::Client::new())
+}
+
+async fn get_json(client: &reqwest::Client, url: &str) -> (StatusCode, Value) {
+    let resp = client.get(url).send().await.unwrap();
+    let status = resp.status();
+    (status, resp.json().await.unwrap())
+}
+
+async fn login(client: &reqwest::Client, api: &str) -> String {
+    let resp = client
+        .post(format!("{}/api/login", api))
+        .json(&json!({ "user": USERNAME, "pass": PASSWORD }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body: Value = resp.json().await.unwrap();
+    body["token"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn bearer_login_then_fetch_pages() {
+    let (hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+
+    let name: Value = client
+        .get(format!("{}/api/name", api))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(name, json!({ "name": "Rivera, Sofia A" }));
+
+    let info: Value = client
+        .get(format!("{}/api/info", api))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(info["school"], "Example High School");
+
+    assert_eq!(hac.logins(), 1);
+}
+
+#[tokio::test]
+async fn wrong_password_is_invalid_credentials() {
+    let (hac, api, client) = setup().await;
+
+    let (status, body) = get_json(&client, &format!("{}/api/name?user={}&pass=wrong", api, USERNAME)).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "invalid_credentials");
+    assert_eq!(hac.logins(), 0);
+}
+
+#[tokio::test]
+async fn query_credentials_are_marked_deprecated() {
+    let (_hac, api, client) = setup().await;
+
+    let resp = client
+        .get(format!("{}/api/name?user={}&pass={}", api, USERNAME, PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["deprecation"], "true");
+    assert!(resp.headers()["warning"].to_str().unwrap().contains("/api/login"));
+
+    let token = login(&client, &api).await;
+    let resp = client.get(format!("{}/api/name", api)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(!resp.headers().contains_key("deprecation"));
+}
+
+#[tokio::test]
+async fn wrong_password_does_not_reuse_cached_login() {
+    let (hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+    let resp = client.get(format!("{}/api/name", api)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let (status, body) = get_json(&client, &format!("{}/api/name?user={}&pass=wrong", api, USERNAME)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "invalid_credentials");
+
+    let resp = client
+        .post(format!("{}/api/login", api))
+        .json(&json!({ "user": USERNAME, "pass": "wrong" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(hac.logins(), 1);
+}
+
+#[tokio::test]
+async fn failing_logon_is_not_reported_as_wrong_password() {
+    let (hac, api, client) = setup().await;
+    let login_with = |client: &reqwest::Client| {
+        client
+            .post(format!("{}/api/login", api))
+            .json(&json!({ "user": USERNAME, "pass": PASSWORD }))
+            .send()
+    };
+
+    hac.set_logon_error(Some(StatusCode::INTERNAL_SERVER_ERROR));
+    let resp = login_with(&client).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "upstream_unavailable");
+
+    hac.set_logon_error(Some(StatusCode::TOO_MANY_REQUESTS));
+    let resp = login_with(&client).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "rate_limited");
+
+    hac.set_logon_error(None);
+    assert_eq!(login_with(&client).await.unwrap().status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn query_credentials_with_district() {
+    let (_hac, api, client) = setup().await;
+    let creds = format!("user={}&pass={}&district=mock", USERNAME, PASSWORD);
+
+    let (status, averages) = get_json(&client, &format!("{}/api/averages?{}&short=true", api, creds)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(averages["ENGLISH 2 AP S1"], "77.50");
+
+    let (_, typed) = get_json(&client, &format!("{}/api/gradebook?{}&format=typed", api, creds)).await;
+    assert_eq!(typed["2301A - 1    ENGLISH 2 AP S1"]["average"]["value"], 77.5);
+
+    let (_, transcript) = get_json(&client, &format!("{}/api/rank?{}", api, creds)).await;
+    assert_eq!(transcript["rank"], "57 / 812");
+
+    let (status, _) = get_json(&client, &format!("{}/api/reportcard?{}", api, creds)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = get_json(&client, &format!("{}/api/ipr?{}", api, creds)).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn unknown_formats_are_rejected_on_every_endpoint() {
+    let (hac, api, client) = setup().await;
+    let creds = format!("user={}&pass={}", USERNAME, PASSWORD);
+
+    for path in ["/api/classes", "/api/gpa", "/api/name", "/api/schedule", "/api/reportcard/runs", "/api/changes"] {
+        for format in ["typed", "garbage"] {
+            let (status, body) = get_json(&client, &format!("{}{}?{}&format={}", api, path, creds, format)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{} format={}", path, format);
+            assert_eq!(body["code"], "bad_request");
+        }
+
+        let (status, _) = get_json(&client, &format!("{}{}?{}&format=raw", api, path, creds)).await;
+        assert_eq!(status, StatusCode::OK, "{} format=raw", path);
+    }
+
+    let (status, _) = get_json(&client, &format!("{}/api/assignments?{}&format=garbage", api, creds)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get_json(&client, &format!("{}/api/assignments?{}&format=typed", api, creds)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Rejected before logging in to HAC.
+    assert_eq!(hac.logins(), 1);
+}
+
+#[tokio::test]
+async fn pages_are_served_from_cache() {
+    let (hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+
+    for _ in 0..2 {
+        let resp = client.get(format!("{}/api/classes", api)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+    assert_eq!(hac.page_hits("Assignments.aspx"), 1);
+
+    let resp = client
+        .get(format!("{}/api/classes?no_cache=true", api))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(hac.page_hits("Assignments.aspx"), 2);
+}
+
+#[tokio::test]
+async fn six_weeks_posts_viewstate_back() {
+    let (hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+
+    let resp = client
+        .get(format!("{}/api/assignments?six_weeks=3", api))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let postbacks = hac.postbacks();
+    assert_eq!(postbacks.len(), 1);
+    let (page, form) = &postbacks[0];
+    assert_eq!(page, "Assignments.aspx");
+    assert_eq!(form["__EVENTTARGET"], "ctl00$plnMain$btnRefreshView");
+    assert!(form["ctl00$plnMain$ddlReportCardRuns"].starts_with("3-"));
+}
+
+#[tokio::test]
+async fn six_weeks_pages_are_cached_longer_once_closed() {
+    let hac = MockHac::start().await;
+    let mut state = api_state(&hac.base_url);
+    // Page data expires right away; only closed runs (before run 2 in the fixture) stay cached.
+    state.cache = Cache::new(30 * 60, 0, 60 * 60);
+    let api = serve(create_router(state)).await;
+    let client = reqwest::Client::new();
+    let token = login(&client, &api).await;
+
+    for six_weeks in ["1", "1", "3", "3"] {
+        let resp = client
+            .get(format!("{}/api/assignments?six_weeks={}", api, six_weeks))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+    assert_eq!(hac.postbacks().len(), 3);
+
+    let resp = client
+        .get(format!("{}/api/assignments?six_weeks=1&no_cache=true", api))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(hac.postbacks().len(), 4);
+}
+
+#[tokio::test]
+async fn six_weeks_viewstate_comes_from_the_posting_session() {
+    let (hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+
+    let resp = client.get(format!("{}/api/assignments", api)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // The cached default page now carries the first session's viewstate.
+    hac.expire_sessions();
+    let resp = client
+        .get(format!("{}/api/assignments?six_weeks=3", api))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(hac.logins(), 2);
+    assert_eq!(hac.postbacks().len(), 1);
+}
+
+#[tokio::test]
+async fn expired_hac_session_logs_in_again() {
+    let (hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+
+    hac.expire_sessions();
+
+    let resp = client.get(format!("{}/api/info", api)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(hac.logins(), 2);
+}
+
+#[tokio::test]
+async fn logout_revokes_token() {
+    let (_hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+
+    let resp = client.post(format!("{}/api/logout", api)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = client.get(format!("{}/api/name", api)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "session_expired");
+}
+
+#[tokio::test]
+async fn login_options_come_from_the_form() {
+    let (_hac, api, client) = setup().await;
+
+    let (status, body) = get_json(&client, &format!("{}/api/login-options?district=mock", api)).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["databases"][0], json!({ "value": "10", "label": "Mock ISD", "selected": true }));
+    assert_eq!(body["verification_options"][1]["value"], "Pin");
+}
+
+#[tokio::test]
+async fn what_if_projects_class_average() {
+    let (_hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+
+    let resp = client
+        .post(format!("{}/api/whatif", api))
+        .bearer_auth(&token)
+        .json(&json!({ "class": "calculus", "edits": [{ "name": "Derivatives Quiz", "score": 50 }] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let result: Value = resp.json().await.unwrap();
+    assert_eq!(result["class"], "5440 - 3    AP CALCULUS BC S1");
+    assert_eq!(result["reported_average"]["value"], 88.6);
+    assert_eq!(result["current_average"], 88.6);
+    assert_eq!(result["projected_average"], 91.0);
+
+    let resp = client
+        .post(format!("{}/api/whatif", api))
+        .bearer_auth(&token)
+        .json(&json!({ "class": "calculus", "drops": ["Final Exam"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "bad_request");
+}
+
+#[tokio::test]
+async fn gpa_is_projected_from_averages() {
+    let (_hac, api, client) = setup().await;
+
+    let (status, gpa) = get_json(&client, &format!("{}/api/gpa?user={}&pass={}&short=true", api, USERNAME, PASSWORD)).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(gpa["unweighted"], 2.5);
+    assert_eq!(gpa["weighted"], 3.5);
+    assert_eq!(gpa["classes"][1]["class"], "ENGLISH 2 AP S1");
+    assert_eq!(gpa["classes"][1]["level"], "AP");
+    assert_eq!(gpa["classes"][1]["weighted_points"], 3.0);
+}
+
+#[tokio::test]
+async fn report_card_runs_and_run_selection() {
+    let (hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+
+    let runs: Value = client
+        .get(format!("{}/api/reportcard/runs", api))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(runs[1], json!({ "value": "2-2026", "label": "2", "selected": true }));
+
+    let resp = client
+        .get(format!("{}/api/reportcard?run=2&format=typed", api))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(hac.postbacks().is_empty(), "selected run needs no postback");
+
+    for _ in 0..2 {
+        let resp = client
+            .get(format!("{}/api/reportcard?run=1", api))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    let postbacks = hac.postbacks();
+    assert_eq!(postbacks.len(), 1, "second request for the run is cached");
+    let (page, form) = &postbacks[0];
+    assert_eq!(page, "ReportCards.aspx");
+    assert_eq!(form["__EVENTTARGET"], "ctl00$plnMain$ddlRCRuns");
+    assert_eq!(form["ctl00$plnMain$ddlRCRuns"], "1-2026");
+
+    let resp = client
+        .get(format!("{}/api/reportcard?run=9", api))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn report_card_run_posts_form_state_from_the_current_session() {
+    let (hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+
+    let resp = client.get(format!("{}/api/reportcard", api)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // The cached report card page now carries the first session's viewstate.
+    hac.expire_sessions();
+    let resp = client.get(format!("{}/api/reportcard?run=1", api)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(hac.logins(), 2);
+    assert_eq!(hac.postbacks().len(), 1);
+}
+
+#[tokio::test]
+async fn progress_report_dates_and_date_selection() {
+    let (hac, api, client) = setup().await;
+    let creds = format!("user={}&pass={}", USERNAME, PASSWORD);
+
+    let (status, dates) = get_json(&client, &format!("{}/api/ipr/dates?{}", api, creds)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(dates[0]["value"], "10/24/2025");
+    assert_eq!(dates[0]["selected"], true);
+
+    let (status, entries) = get_json(&client, &format!("{}/api/ipr?{}&date=09/12/2025&format=typed", api, creds)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(entries[1]["mark"]["value"], 88.0);
+
+    let postbacks = hac.postbacks();
+    assert_eq!(postbacks.len(), 1);
+    let (page, form) = &postbacks[0];
+    assert_eq!(page, "InterimProgress.aspx");
+    assert_eq!(form["__EVENTTARGET"], "ctl00$plnMain$ddlIPRDates");
+    assert_eq!(form["ctl00$plnMain$ddlIPRDates"], "09/12/2025");
+}
+
+#[tokio::test]
+async fn progress_date_posts_form_state_from_the_current_session() {
+    let (hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+
+    let resp = client.get(format!("{}/api/ipr", api)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    hac.expire_sessions();
+    let resp = client
+        .get(format!("{}/api/ipr?date=09/12/2025", api))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(hac.logins(), 2);
+    assert_eq!(hac.postbacks().len(), 1);
+}
+
+#[tokio::test]
+async fn graduation_audit_combines_transcript_and_current_classes() {
+    let (_hac, api, client) = setup().await;
+
+    let (status, audit) = get_json(&client, &format!("{}/api/graduation?user={}&pass={}", api, USERNAME, PASSWORD)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let english = audit["requirements"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["subject"] == "English")
+        .unwrap();
+    assert_eq!(english["completed"], 1.5);
+    assert_eq!(english["in_progress"], 0.5);
+    assert_eq!(english["remaining"], 2.0);
+    assert_eq!(audit["completed"], 4.0);
+    assert_eq!(audit["in_progress"], 1.0);
+}
+
+#[tokio::test]
+async fn schedule_lists_current_classes() {
+    let (_hac, api, client) = setup().await;
+
+    let (status, schedule) = get_json(&client, &format!("{}/api/schedule?user={}&pass={}", api, USERNAME, PASSWORD)).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(schedule.as_array().unwrap().len(), 3);
+    assert_eq!(schedule[1]["teacher_email"], "daniel.okafor@example.org");
+}
+
+#[tokio::test]
+async fn gpa_rules_and_graduation_plan_come_from_app_state() {
+    let hac = MockHac::start().await;
+    let client = reqwest::Client::new();
+    let rules = GpaRules::parse(r#"{ "levels": [], "bands": [{ "min": 0, "points": 4 }] }"#).unwrap();
+    let plan = GraduationPlan::parse(r#"{ "name": "Custom", "requirements": [{ "subject": "English", "credits": 2, "keywords": ["ENGLISH"] }] }"#).unwrap();
+    let custom = serve(create_router(AppState {
+        gpa_rules: Arc::new(rules),
+        graduation_plan: Arc::new(plan),
+        ..api_state(&hac.base_url)
+    }))
+    .await;
+    let builtin = spawn_api(&hac.base_url).await;
+    let creds = format!("user={}&pass={}", USERNAME, PASSWORD);
+
+    let (_, gpa) = get_json(&client, &format!("{}/api/gpa?{}", custom, creds)).await;
+    assert_eq!((gpa["unweighted"].as_f64(), gpa["weighted"].as_f64()), (Some(4.0), Some(4.0)));
+    let (_, gpa) = get_json(&client, &format!("{}/api/gpa?{}", builtin, creds)).await;
+    assert_eq!((gpa["unweighted"].as_f64(), gpa["weighted"].as_f64()), (Some(2.5), Some(3.5)));
+
+    let (_, audit) = get_json(&client, &format!("{}/api/graduation?{}", custom, creds)).await;
+    assert_eq!(audit["plan"], "Custom");
+    assert_eq!(audit["requirements"].as_array().unwrap().len(), 1);
+    let (_, audit) = get_json(&client, &format!("{}/api/graduation?{}", builtin, creds)).await;
+    assert_eq!(audit["plan"], "Texas Foundation with Endorsement");
+}
+
+#[tokio::test]
+async fn attendance_walks_months_with_postbacks() {
+    let (hac, api, client) = setup().await;
+    let creds = format!("user={}&pass={}", USERNAME, PASSWORD);
+
+    let (status, report) =
+        get_json(&client, &format!("{}/api/attendance?{}&from=2025-09-15&to=2025-10-21", api, creds)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let dates: Vec<&str> = report["days"].as_array().unwrap().iter().map(|d| d["date"].as_str().unwrap()).collect();
+    assert_eq!(dates, ["2025-09-19", "2025-10-07", "2025-10-14", "2025-10-21"]);
+    assert_eq!(report["totals"]["AE"], json!({ "description": "Excused Absence", "days": 2, "periods": 4 }));
+    assert_eq!(report["totals"]["UX"]["days"], 1);
+
+    let postbacks = hac.postbacks();
+    assert_eq!(postbacks.len(), 1);
+    assert_eq!(postbacks[0].1["__EVENTARGUMENT"], "V9375");
+
+    let (status, _) = get_json(&client, &format!("{}/api/attendance?{}&from=2025-11-01&to=2025-10-01", api, creds)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn attendance_months_post_form_state_from_the_current_session() {
+    let (hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+    let attendance = |range: &str| client.get(format!("{}/api/attendance?{}", api, range)).bearer_auth(&token).send();
+
+    let resp = attendance("from=2025-10-01&to=2025-10-21").await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(hac.postbacks().is_empty());
+
+    // The cached calendar page now carries the first session's viewstate.
+    hac.expire_sessions();
+    let resp = attendance("from=2025-09-15&to=2025-10-21").await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(hac.logins(), 2);
+    assert_eq!(hac.postbacks().len(), 1);
+}
+
+#[tokio::test]
+async fn calendar_feed_is_stable_across_refreshes() {
+    let (_hac, api, client) = setup().await;
+    let url = format!("{}/api/calendar.ics?user={}&pass={}", api, USERNAME, PASSWORD);
+
+    let fetch = || async {
+        let resp = client.get(&url).send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "text/calendar; charset=utf-8");
+        resp.text().await.unwrap()
+    };
+
+    let uids = |ics: &str| -> Vec<String> {
+        ics.lines().filter_map(|l| l.strip_prefix("UID:")).map(str::to_string).collect()
+    };
+
+    let first = fetch().await;
+    let second = fetch().await;
+    assert_eq!(uids(&first).len(), 8);
+    assert_eq!(uids(&first), uids(&second));
+}
+
+#[tokio::test]
+async fn changes_between_gradebook_snapshots() {
+    let (hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+
+    let changes = |query: &str| {
+        let request = client.get(format!("{}/api/changes?{}", api, query)).bearer_auth(&token);
+        async move {
+            let resp = request.send().await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            resp.json::<Value>().await.unwrap()
+        }
+    };
+
+    let first = changes("").await;
+    assert_eq!(first["previous_etag"], Value::Null);
+    assert_eq!(first["changes"], json!([]));
+
+    let unchanged = changes("no_cache=true").await;
+    assert_eq!(unchanged["etag"], first["etag"]);
+    assert_eq!(unchanged["previous_etag"], first["etag"]);
+    assert_eq!(unchanged["changes"], json!([]));
+
+    let graded = fixture("Assignments.aspx")
+        .replace("Classwork Average 77.50", "Classwork Average 76.25")
+        .replace(
+            "<td>Major</td><td></td><td>100.00</td><td>1.00</td><td></td><td>100.00</td><td></td>",
+            "<td>Major</td><td>88.00</td><td>100.00</td><td>1.00</td><td>88.00</td><td>100.00</td><td>88.00%</td>",
+        );
+    hac.set_page("Assignments.aspx", graded);
+
+    let updated = changes("no_cache=true&short=true").await;
+    assert_ne!(updated["etag"], first["etag"]);
+    assert_eq!(updated["previous_etag"], first["etag"]);
+    assert_eq!(
+        updated["changes"],
+        json!([
+            { "kind": "average_changed", "class": "ENGLISH 2 AP S1", "assignment": null, "old": "77.50", "new": "76.25", "delta": -1.25 },
+            { "kind": "score_changed", "class": "ENGLISH 2 AP S1", "assignment": "Unit 3 Test", "old": null, "new": "88.00", "delta": null },
+        ])
+    );
+
+    let settled = changes("no_cache=true").await;
+    assert_eq!(settled["changes"], json!([]));
+
+    let replay = changes(&format!("no_cache=true&since={}", first["etag"].as_str().unwrap())).await;
+    assert_eq!(replay["changes"].as_array().unwrap().len(), 2);
+
+    let resp = client.get(format!("{}/api/changes?since=bogus", api)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn changes_are_tracked_per_session() {
+    let (hac, api, client) = setup().await;
+    let tokens = [login(&client, &api).await, login(&client, &api).await];
+
+    let changes = |token: &str| {
+        let request = client.get(format!("{}/api/changes?no_cache=true", api)).bearer_auth(token);
+        async move {
+            let resp = request.send().await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            resp.json::<Value>().await.unwrap()
+        }
+    };
+
+    let first = changes(&tokens[0]).await;
+    for token in &tokens {
+        assert_eq!(changes(token).await["etag"], first["etag"]);
+    }
+
+    hac.set_page("Assignments.aspx", fixture("Assignments.aspx").replace("Classwork Average 77.50", "Classwork Average 76.25"));
+
+    // Neither session's poll uses up the change for the other.
+    for token in &tokens {
+        let update = changes(token).await;
+        assert_eq!(update["previous_etag"], first["etag"]);
+        assert_eq!(update["changes"].as_array().unwrap().len(), 1);
+        assert_eq!(update["changes"][0]["kind"], "average_changed");
+    }
+
+    // A new session starts its own history.
+    let token = login(&client, &api).await;
+    let resp = client
+        .get(format!("{}/api/changes?since={}", api, first["etag"].as_str().unwrap()))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+#[derive(Clone, Default)]
+struct Receiver {
+    requests: Arc<Mutex<Vec<(axum::http::HeaderMap, String)>>>,
+    failures: Arc<AtomicUsize>,
+}
+
+async fn receive(
+    axum::extract::State(receiver): axum::extract::State<Receiver>,
+    headers: axum::http::HeaderMap,
+    body: String,
+) -> axum::http::StatusCode {
+    receiver.requests.lock().unwrap().push((headers, body));
+    let failing = receiver.failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok();
+    if failing {
+        axum::http::StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        axum::http::StatusCode::NO_CONTENT
+    }
+}
+
+async fn poll(state: &AppState) -> usize {
+    let mut delivered = 0;
+    for delivery in webhooks::poll_once(&state.cache, &state.webhooks).await {
+        delivered += delivery.await.unwrap();
+    }
+    delivered
+}
+
+#[tokio::test]
+async fn webhooks_deliver_signed_grade_changes() {
+    let hac = common::mock_hac::MockHac::start().await;
+    let state = api_state(&hac.base_url);
+    let api = serve(create_router(state.clone())).await;
+    let client = reqwest::Client::new();
+    let token = login(&client, &api).await;
+
+    let receiver = Receiver::default();
+    let hook = format!(
+        "{}/hook",
+        serve(axum::Router::new().route("/hook", axum::routing::post(receive)).with_state(receiver.clone())).await
+    );
+
+    let resp = client
+        .post(format!("{}/api/subscriptions", api))
+        .bearer_auth(&token)
+        .json(&json!({ "url": hook, "secret": "s3cret", "short": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let subscription: Value = resp.json().await.unwrap();
+    assert_eq!(subscription["secret"], "s3cret");
+
+    let bad = client
+        .post(format!("{}/api/subscriptions", api))
+        .bearer_auth(&token)
+        .json(&json!({ "url": "ftp://example.com/hook" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(bad.status(), StatusCode::BAD_REQUEST);
+
+    assert_eq!(poll(&state).await, 0);
+    assert!(receiver.requests.lock().unwrap().is_empty());
+
+    let graded = fixture("Assignments.aspx").replace(
+        "<td>Major</td><td></td><td>100.00</td><td>1.00</td><td></td><td>100.00</td><td></td>",
+        "<td>Major</td><td>88.00</td><td>100.00</td><td>1.00</td><td>88.00</td><td>100.00</td><td>88.00%</td>",
+    );
+    hac.set_page("Assignments.aspx", graded);
+    receiver.failures.store(1, Ordering::SeqCst);
+
+    assert_eq!(poll(&state).await, 1);
+    let requests = receiver.requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].0[DELIVERY_HEADER], requests[1].0[DELIVERY_HEADER]);
+
+    let (headers, body) = &requests[1];
+    assert_eq!(headers[SIGNATURE_HEADER], webhooks::sign("s3cret", body.as_bytes()).as_str());
+    let payload: Value = serde_json::from_str(body).unwrap();
+    assert_eq!(payload["event"], "grade.changed");
+    assert_eq!(payload["subscription"], subscription["id"]);
+    assert_eq!(
+        payload["change"],
+        json!({ "kind": "score_changed", "class": "ENGLISH 2 AP S1", "assignment": "Unit 3 Test", "old": null, "new": "88.00", "delta": null })
+    );
+
+    assert_eq!(poll(&state).await, 0);
+    assert_eq!(hac.logins(), 1);
+
+    // A receiver that stays down doesn't lose the change: the baseline waits for a successful delivery.
+    hac.set_page("Assignments.aspx", fixture("Assignments.aspx"));
+    receiver.failures.store(3, Ordering::SeqCst);
+    assert_eq!(poll(&state).await, 0);
+    assert_eq!(poll(&state).await, 1);
+    assert_eq!(poll(&state).await, 0);
+
+    let (status, list) = get_json(&client, &format!("{}/api/subscriptions?user={}&pass={}", api, USERNAME, PASSWORD)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(list.as_array().unwrap().len(), 1);
+
+    let resp = client
+        .delete(format!("{}/api/subscriptions/{}", api, subscription["id"].as_str().unwrap()))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(poll(&state).await, 0);
+}
+
+#[tokio::test]
+async fn webhook_subscriptions_check_credentials_and_destination() {
+    let hac = MockHac::start().await;
+    let state = api_state(&hac.base_url);
+    let api = serve(create_router(state.clone())).await;
+    let client = reqwest::Client::new();
+    let hook = format!(
+        "{}/hook",
+        serve(axum::Router::new().route("/hook", axum::routing::post(receive)).with_state(Receiver::default())).await
+    );
+    let subscribe = |pass: &str| {
+        client
+            .post(format!("{}/api/subscriptions?user={}&pass={}", api, USERNAME, pass))
+            .json(&json!({ "url": hook }))
+            .send()
+    };
+
+    assert_eq!(subscribe("wrong").await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    let (status, _) = get_json(&client, &format!("{}/api/subscriptions?user={}&pass=wrong", api, USERNAME)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    assert_eq!(subscribe(PASSWORD).await.unwrap().status(), StatusCode::CREATED);
+    assert_eq!(poll(&state).await, 0);
+
+    hac.set_password("changed");
+    hac.expire_sessions();
+    assert_eq!(poll(&state).await, 0);
+    let logins = hac.logins();
+    assert_eq!(poll(&state).await, 0);
+    assert_eq!(hac.logins(), logins);
+
+    let (status, list) = get_json(&client, &format!("{}/api/subscriptions?user={}&pass=changed", api, USERNAME)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(list[0]["active"], false);
+
+    let strict = serve(create_router(AppState {
+        webhooks: webhooks::Webhooks::new(webhooks::Settings::default()),
+        ..api_state(&hac.base_url)
+    }))
+    .await;
+    for url in ["http://127.0.0.1:9/hook", "http://localhost/hook", "http://[::1]/hook", "http://169.254.169.254/latest"] {
+        let resp = client
+            .post(format!("{}/api/subscriptions?user={}&pass=changed", strict, USERNAME))
+            .json(&json!({ "url": url }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{}", url);
+    }
+}
+
+#[tokio::test]
+async fn transient_upstream_errors_are_retried() {
+    let (hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+
+    hac.fail_next(2);
+    let resp = client.get(format!("{}/api/info", api)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(hac.page_hits("Registration.aspx"), 3);
+
+    let metrics = client.get(format!("{}/metrics", api)).send().await.unwrap().text().await.unwrap();
+    assert!(metrics.contains(&format!("hac_upstream_retries_total{{upstream=\"{}\"}} 2", hac.base_url)), "{}", metrics);
+}
+
+#[tokio::test]
+async fn circuit_breaker_fails_fast_when_hac_is_down() {
+    let (hac, api, client) = setup().await;
+    let token = login(&client, &api).await;
+    hac.set_down(true);
+
+    for _ in 0..5 {
+        let resp = client.get(format!("{}/api/info", api)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+    }
+    let hits = hac.page_hits("Registration.aspx");
+    assert_eq!(hits, 15);
+
+    let resp = client.get(format!("{}/api/info", api)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(resp.headers().contains_key("retry-after"));
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "upstream_circuit_open");
+    assert_eq!(body["upstream"], hac.base_url);
+    assert_eq!(hac.page_hits("Registration.aspx"), hits);
+
+    let metrics = client.get(format!("{}/metrics", api)).send().await.unwrap().text().await.unwrap();
+    assert!(metrics.contains(&format!("hac_upstream_circuit_open{{upstream=\"{}\"}} 1", hac.base_url)), "{}", metrics);
+    assert!(metrics.contains(&format!("hac_upstream_rejected_total{{upstream=\"{}\"}} 1", hac.base_url)), "{}", metrics);
+}
+
+#[tokio::test]
+async fn custom_links_share_one_upstream_entry() {
+    let (_hac, api, client) = setup().await;
+    let others = [MockHac::start().await, MockHac::start().await];
+
+    for other in &others {
+        let url = format!("{}/api/info?user={}&pass={}&link={}", api, USERNAME, PASSWORD, other.base_url);
+        let (status, _) = get_json(&client, &url).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let metrics = client.get(format!("{}/metrics", api)).send().await.unwrap().text().await.unwrap();
+    assert!(metrics.contains("hac_upstream_requests_total{upstream=\"custom\"}"), "{}", metrics);
+    for other in &others {
+        assert!(!metrics.contains(&other.base_url), "{}", metrics);
+    }
+}
+
+#[tokio::test]
+async fn concurrent_requests_share_login_and_page_fetch() {
+    let (hac, api, client) = setup().await;
+    hac.set_latency(Duration::from_millis(200));
+    let creds = format!("user={}&pass={}", USERNAME, PASSWORD);
+
+    let [averages, assignments, weightings] = ["averages", "assignments", "weightings"]
+        .map(|endpoint| client.get(format!("{}/api/{}?{}", api, endpoint, creds)).send());
+    let responses = tokio::join!(averages, assignments, weightings);
+    for resp in [responses.0, responses.1, responses.2] {
+        assert_eq!(resp.unwrap().status(), StatusCode::OK);
+    }
+
+    assert_eq!(hac.logins(), 1);
+    assert_eq!(hac.page_hits("Assignments.aspx"), 1);
+
+    let (status, _) = get_json(&client, &format!("{}/api/averages?{}&no_cache=true", api, creds)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(hac.logins(), 2);
+    assert_eq!(hac.page_hits("Assignments.aspx"), 2);
+}
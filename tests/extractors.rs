@@ -0,0 +1,126 @@
+mod common;
+
+use common::{assert_snapshot, fixture};
+use hac_api::calendar::assignments_calendar;
+use hac_api::scraping::*;
+
+#[test]
+fn name_from_classwork() {
+    assert_snapshot("name", &extract_name(&fixture("Classwork.html")));
+}
+
+#[test]
+fn info_from_registration() {
+    assert_snapshot("info", &extract_info(&fixture("Registration.aspx")));
+}
+
+#[test]
+fn classes_from_assignments() {
+    let html = fixture("Assignments.aspx");
+    assert_snapshot("classes", &extract_classes(&html, false));
+    assert_snapshot("classes_short", &extract_classes(&html, true));
+}
+
+#[test]
+fn averages_from_assignments() {
+    let html = fixture("Assignments.aspx");
+    assert_snapshot("averages", &extract_averages(&html, false));
+    assert_snapshot("averages_typed", &extract_averages_typed(&html, true));
+}
+
+#[test]
+fn assignments_from_assignments() {
+    let html = fixture("Assignments.aspx");
+    assert_snapshot("assignments", &extract_assignments(&html, false));
+    assert_snapshot("assignments_typed", &extract_assignments_typed(&html, false));
+}
+
+#[test]
+fn weightings_from_assignments() {
+    let html = fixture("Assignments.aspx");
+    assert_snapshot("weightings", &extract_weightings(&html, false));
+    assert_snapshot("weightings_typed", &extract_weightings_typed(&html, false));
+}
+
+#[test]
+fn gradebook_from_assignments() {
+    let html = fixture("Assignments.aspx");
+    assert_snapshot("gradebook", &extract_gradebook(&html, true));
+    assert_snapshot("gradebook_typed", &extract_gradebook_typed(&html, false));
+}
+
+#[test]
+fn schedule_from_classes() {
+    assert_snapshot("schedule", &extract_schedule(&fixture("Classes.aspx")));
+}
+
+#[test]
+fn report_cards() {
+    let html = fixture("ReportCards.aspx");
+    assert_snapshot("report_cards", &extract_report_cards(&html));
+    assert_snapshot("report_cards_typed", &extract_report_cards_typed(&html));
+    assert_snapshot("report_card_runs", &extract_report_card_runs(&html));
+}
+
+#[test]
+fn report_cards_with_quarters() {
+    let html = fixture("ReportCardsQuarters.aspx");
+    assert_snapshot("report_cards_quarters", &extract_report_cards(&html));
+    assert_snapshot("report_cards_quarters_typed", &extract_report_cards_typed(&html));
+}
+
+#[test]
+fn progress_report() {
+    let html = fixture("InterimProgress.aspx");
+    assert_snapshot("progress", &extract_progress(&html));
+    assert_snapshot("progress_typed", &extract_progress_typed(&html));
+    assert_snapshot("progress_dates", &extract_progress_dates(&html));
+}
+
+#[test]
+fn transcript() {
+    let html = fixture("Transcript.aspx");
+    assert_snapshot("transcript", &extract_transcript(&html));
+    assert_snapshot("transcript_typed", &extract_transcript_typed(&html));
+}
+
+#[test]
+fn rank_from_transcript() {
+    assert_snapshot("rank", &extract_rank(&fixture("Transcript.aspx")));
+}
+
+#[test]
+fn attendance_month() {
+    assert_snapshot("attendance_october", &extract_attendance_month(&fixture("MonthlyView.aspx")));
+    assert_snapshot("attendance_september", &extract_attendance_month(&fixture("MonthlyView_2025-09.aspx")));
+}
+
+#[test]
+fn assignments_calendar_events() {
+    let html = fixture("Assignments.aspx");
+    let stamp = chrono::DateTime::from_timestamp(1_760_000_000, 0).unwrap();
+    let ics = assignments_calendar(&html, false, "student@mock", stamp);
+
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    assert!(ics.split("\r\n").all(|line| line.len() <= 75));
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 8);
+
+    let unfolded = ics.replace("\r\n ", "");
+    assert!(unfolded.contains("DTSTART;VALUE=DATE:20251003\r\nDTEND;VALUE=DATE:20251004\r\n"));
+    assert!(unfolded.contains("SUMMARY:2301A - 1    ENGLISH 2 AP S1: Rhetorical Analysis Essay\r\n"));
+    assert!(unfolded.contains("DESCRIPTION:Category: Major\\nScore: 92.00/100\\nPercentage: 92.00%\r\n"));
+
+    let uids = |ics: &str| -> Vec<String> {
+        ics.lines().filter_map(|l| l.strip_prefix("UID:")).map(str::to_string).collect()
+    };
+    let mut unique = uids(&ics);
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), 8);
+
+    let moved = assignments_calendar(&html.replace("10/03/2025", "10/06/2025"), false, "student@mock", stamp);
+    assert_eq!(uids(&moved), uids(&ics));
+    assert!(moved.contains("DTSTART;VALUE=DATE:20251006"));
+    assert_ne!(uids(&assignments_calendar(&html, false, "other@mock", stamp)), uids(&ics));
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::fixture;
+use hac_api::gpa::{project_gpa, GpaRules};
+
+fn gradebook_with(replacements: &[(&str, &str)]) -> String {
+    replacements
+        .iter()
+        .fold(fixture("Assignments.aspx"), |html, (from, to)| html.replace(from, to))
+}
+
+#[test]
+fn levels_from_keywords() {
+    let rules = GpaRules::default();
+
+    assert_eq!(rules.level_for("2301A - 1    ENGLISH 2 AP S1"), ("AP", 1.0));
+    assert_eq!(rules.level_for("IB HISTORY OF AMERICAS"), ("IB", 1.0));
+    assert_eq!(rules.level_for("CHEMISTRY HONORS"), ("Advanced", 0.5));
+    assert_eq!(rules.level_for("Pre-AP Biology"), ("Advanced", 0.5));
+    assert_eq!(rules.level_for("APPLIED MATH"), ("Regular", 0.0));
+    assert_eq!(rules.level_for("WORLD GEOGRAPHY"), ("Regular", 0.0));
+}
+
+#[test]
+fn levels_from_code_prefixes() {
+    let rules = GpaRules::parse(
+        r#"{
+            "levels": [{ "name": "Weighted", "code_prefixes": ["a"], "bonus": 1.5 }],
+            "bands": [{ "min": 0, "points": 4 }],
+            "default_level": "Standard"
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(rules.level_for("A5440 - 3    CALCULUS"), ("Weighted", 1.5));
+    assert_eq!(rules.level_for("5440A - 3    ALGEBRA"), ("Standard", 0.0));
+}
+
+#[test]
+fn points_from_grade_bands() {
+    let rules = GpaRules::default();
+    assert_eq!(rules.base_points(100.0), 4.0);
+    assert_eq!(rules.base_points(90.0), 4.0);
+    assert_eq!(rules.base_points(89.99), 3.0);
+    assert_eq!(rules.base_points(70.0), 2.0);
+    assert_eq!(rules.base_points(69.9), 0.0);
+
+    // Bands may be listed in any order.
+    let rules = GpaRules::parse(r#"{ "levels": [], "bands": [{ "min": 60, "points": 1 }, { "min": 75, "points": 2.5 }] }"#).unwrap();
+    assert_eq!(rules.base_points(80.0), 2.5);
+    assert_eq!(rules.base_points(60.0), 1.0);
+}
+
+#[test]
+fn gpa_from_gradebook() {
+    let report = project_gpa(&GpaRules::default(), &fixture("Assignments.aspx"), true);
+
+    assert_eq!(report.classes.len(), 2);
+    assert_eq!(report.classes[0].class, "AP CALCULUS BC S1");
+    assert_eq!(report.classes[0].level, "AP");
+    assert_eq!(report.classes[0].unweighted_points, Some(3.0));
+    assert_eq!(report.classes[0].weighted_points, Some(4.0));
+    assert_eq!(report.unweighted, Some(2.5));
+    assert_eq!(report.weighted, Some(3.5));
+}
+
+#[test]
+fn weighted_and_unweighted_means() {
+    let html = gradebook_with(&[
+        ("ENGLISH 2 AP S1", "ENGLISH 2 S1"),
+        ("AP CALCULUS BC S1", "CALCULUS BC HONORS S1"),
+        ("Classwork Average 88.60", "Classwork Average 85.00"),
+    ]);
+    let report = project_gpa(&GpaRules::default(), &html, false);
+
+    let levels: Vec<_> = report.classes.iter().map(|c| c.level.as_str()).collect();
+    assert_eq!(levels, ["Regular", "Advanced"]);
+    assert_eq!(report.unweighted, Some(2.5));
+    assert_eq!(report.weighted, Some(2.75));
+}
+
+#[test]
+fn failing_classes_get_no_bonus() {
+    let html = gradebook_with(&[("Classwork Average 88.60", "Classwork Average 65.00")]);
+    let report = project_gpa(&GpaRules::default(), &html, true);
+
+    let calculus = &report.classes[0];
+    assert_eq!(calculus.level, "AP");
+    assert_eq!(calculus.unweighted_points, Some(0.0));
+    assert_eq!(calculus.weighted_points, Some(0.0));
+    assert_eq!(report.unweighted, Some(1.0));
+    assert_eq!(report.weighted, Some(1.5));
+}
+
+#[test]
+fn ungraded_classes_are_left_out() {
+    let html = gradebook_with(&[("Classwork Average 88.60", "Classwork Average ")]);
+    let report = project_gpa(&GpaRules::default(), &html, true);
+
+    assert_eq!(report.classes[0].unweighted_points, None);
+    assert_eq!(report.unweighted, Some(2.0));
+    assert_eq!(report.weighted, Some(3.0));
+}
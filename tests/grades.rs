@@ -0,0 +1,30 @@
+use hac_api::grades::{parse_number, Grade, Score};
+
+#[test]
+fn scores_parse_by_kind() {
+    assert_eq!(Score::parse("95.50"), Score::Points(95.5));
+    assert_eq!(Score::parse(" 88% "), Score::Points(88.0));
+    assert_eq!(Score::parse("b+"), Score::Letter("B+".to_string()));
+    assert_eq!(Score::parse("X"), Score::Exempt);
+    assert_eq!(Score::parse("msg"), Score::Missing);
+    assert_eq!(Score::parse("INC"), Score::Incomplete);
+    assert_eq!(Score::parse(""), Score::NotGraded);
+    assert_eq!(Score::parse("N/A"), Score::NotGraded);
+    assert_eq!(Score::parse("Late"), Score::Other("Late".to_string()));
+}
+
+#[test]
+fn non_finite_numbers_are_not_points() {
+    for raw in ["NaN", "nan", "inf", "-inf", "infinity", "Infinity%"] {
+        assert!(matches!(Score::parse(raw), Score::Other(_)), "{}", raw);
+        assert_eq!(parse_number(raw), None, "{}", raw);
+    }
+    assert_eq!(Grade::parse("NaN").score.points(), None);
+}
+
+#[test]
+fn numbers_allow_percent_and_thousands() {
+    assert_eq!(parse_number("1,250.5"), Some(1250.5));
+    assert_eq!(parse_number(" 93.25% "), Some(93.25));
+    assert_eq!(parse_number("A"), None);
+}